@@ -0,0 +1,28 @@
+//! UTF-8校验：scan::validateUtf8(AVX2查表法)与标准库逐字节校验的对比。
+//! cargo bench --bench utf8
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use rvalue::dxvalue::scan;
+
+fn measure(name: &str,data: &[u8],f: impl Fn(&[u8]) -> bool){
+    let mut rounds = 0u32;
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_millis(500){
+        assert!(f(black_box(data)));
+        rounds += 1;
+    }
+    let secs = start.elapsed().as_secs_f64();
+    println!("{:<24} {:>8.0} MB/s",name,data.len() as f64 * rounds as f64 / secs / 1e6);
+}
+
+fn main(){
+    let ascii = r#"{"id":1234567,"name":"rvalue","tags":["json","simd"],"ok":true},"#.repeat(16 * 1024);
+    let mixed = r#"{"名字":"不得闲","城市":"深圳","note":"mixed 中文 and ASCII 😀"},"#.repeat(16 * 1024);
+    let cjk = "中文字符串的校验速度，全部为三字节序列。".repeat(16 * 1024);
+    for (name,data) in [("ascii",&ascii),("mixed",&mixed),("cjk",&cjk)].iter(){
+        println!("{} ({} KB)",name,data.len() / 1024);
+        measure("  scan::validateUtf8",data.as_bytes(),|b| scan::validateUtf8(b).is_ok());
+        measure("  std::str::from_utf8",data.as_bytes(),|b| std::str::from_utf8(b).is_ok());
+    }
+}
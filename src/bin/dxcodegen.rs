@@ -0,0 +1,79 @@
+//! 根据JSON样本或JSON Schema生成Rust结构体
+//!
+//! dxcodegen [--schema] [--ndjson] [--name Root] [--crate rvalue] [文件...]
+//!
+//! 不指定文件时从标准输入读取；--ndjson时每行是一个样本，--schema时输入是JSON Schema
+
+use std::io::{self, BufReader, Read};
+use std::process;
+use rvalue::dxvalue::codegen::{self, CodegenOptions};
+use rvalue::dxvalue::json::{self, ParseOptions};
+use rvalue::dxvalue::ndjson::NdjsonReader;
+use rvalue::dxvalue::DxValue;
+
+const USAGE: &str = "usage: dxcodegen [--schema] [--ndjson] [--name Root] [--crate rvalue] [file...]";
+
+fn fail(msg: &str) -> ! {
+    eprintln!("dxcodegen: {}", msg);
+    process::exit(1);
+}
+
+fn parse(name: &str, buf: &[u8], ndjson: bool, samples: &mut Vec<DxValue>) {
+    if ndjson {
+        for v in NdjsonReader::new(BufReader::new(buf)) {
+            match v {
+                Ok(v) => samples.push(v),
+                Err(e) => fail(&format!("{}: line {} {:?} at {}", name, e.line(), e.reson(), e.pos())),
+            }
+        }
+        return;
+    }
+    match json::parseJsonWithOptions(buf, &ParseOptions::jsonc()) {
+        Ok(v) => samples.push(v),
+        Err(e) => fail(&format!("{}: {:?} at {}", name, e.reson(), e.pos())),
+    }
+}
+
+fn main() {
+    let mut opts = CodegenOptions::default();
+    let (mut schema, mut ndjson) = (false, false);
+    let mut files = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--schema" => schema = true,
+            "--ndjson" => ndjson = true,
+            "--name" => opts.root_name = args.next().unwrap_or_else(|| fail(USAGE)),
+            "--crate" => opts.crate_path = args.next().unwrap_or_else(|| fail(USAGE)),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            },
+            a if a.starts_with('-') => fail(USAGE),
+            _ => files.push(arg),
+        }
+    }
+    let mut samples = Vec::new();
+    if files.is_empty() {
+        let mut buf = Vec::new();
+        if let Err(e) = io::stdin().read_to_end(&mut buf) {
+            fail(&e.to_string());
+        }
+        parse("<stdin>", &buf, ndjson, &mut samples);
+    }
+    for file in &files {
+        match std::fs::read(file) {
+            Ok(buf) => parse(file, &buf, ndjson, &mut samples),
+            Err(e) => fail(&format!("{}: {}", file, e)),
+        }
+    }
+    let code = if schema {
+        match samples.as_slice() {
+            [schema] => codegen::rustFromSchema(schema, &opts),
+            _ => fail("--schema takes exactly one schema document"),
+        }
+    } else {
+        codegen::rustFromSamples(&samples, &opts)
+    };
+    print!("{}", code);
+}
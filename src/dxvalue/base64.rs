@@ -0,0 +1,63 @@
+//! 标准base64(RFC 4648，带=填充)编解码，用于Binary值和JSON字符串之间的转换
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub fn encode(data: &[u8]) -> String{
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3){
+        let b0 = chunk[0] as u32;
+        let b1 = if chunk.len() > 1 { chunk[1] as u32 } else { 0 };
+        let b2 = if chunk.len() > 2 { chunk[2] as u32 } else { 0 };
+        let n = (b0 << 16) | (b1 << 8) | b2;
+        out.push(ALPHABET[(n >> 18) as usize & 0x3F] as char);
+        out.push(ALPHABET[(n >> 12) as usize & 0x3F] as char);
+        if chunk.len() > 1{
+            out.push(ALPHABET[(n >> 6) as usize & 0x3F] as char);
+        }else{
+            out.push('=');
+        }
+        if chunk.len() > 2{
+            out.push(ALPHABET[n as usize & 0x3F] as char);
+        }else{
+            out.push('=');
+        }
+    }
+    out
+}
+
+fn decode_char(c: u8) -> Option<u32>{
+    match c {
+        b'A'..=b'Z' => Some((c - b'A') as u32),
+        b'a'..=b'z' => Some((c - b'a' + 26) as u32),
+        b'0'..=b'9' => Some((c - b'0' + 52) as u32),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    }
+}
+
+/// 解码base64字符串，末尾的=填充可以省略，遇到非法字符返回None
+pub fn decode(text: &str) -> Option<Vec<u8>>{
+    let mut input = text.as_bytes();
+    while let Some((&b'=', rest)) = input.split_last(){
+        input = rest;
+    }
+    if input.len() % 4 == 1 || text.len() - input.len() > 2{
+        return None;
+    }
+    let mut out = Vec::with_capacity(input.len() / 4 * 3 + 2);
+    for chunk in input.chunks(4){
+        let mut n = 0u32;
+        for (i,&c) in chunk.iter().enumerate(){
+            n |= decode_char(c)? << (18 - 6 * i);
+        }
+        out.push((n >> 16) as u8);
+        if chunk.len() > 2{
+            out.push((n >> 8) as u8);
+        }
+        if chunk.len() > 3{
+            out.push(n as u8);
+        }
+    }
+    Some(out)
+}
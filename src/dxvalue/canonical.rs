@@ -0,0 +1,122 @@
+//! RFC 8785 (JCS) 规范化输出：键按UTF-16编码单元排序，数字按ECMAScript规则输出，
+//! 字符串只做最少的转义，同一个值总是得到相同的字节，用于签名和去重
+
+use std::fmt::Write;
+use crate::dxvalue::{base64, errValueReson, sha256, writeJsonString, DxValue, Key_Value};
+
+/// 输出规范化的JSON。所有数字都按double处理(I-JSON)，超出±2^53的整数、Decimal会丢失精度，
+/// 需要精确保留时应存为字符串；NaN/Infinity返回VET_NonFinite。
+/// 对象中的重复键只保留第一个，与get_byName一致
+pub fn canonicalize(value: &DxValue) -> Result<String,errValueReson>{
+    let mut out = String::new();
+    writeCanonical(&mut out,value)?;
+    Ok(out)
+}
+
+/// 规范化输出的SHA-256
+pub fn contentHash(value: &DxValue) -> Result<[u8; 32],errValueReson>{
+    canonicalize(value).map(|text| sha256::digest(text.as_bytes()))
+}
+
+fn writeCanonical(out: &mut String,value: &DxValue) -> Result<(),errValueReson>{
+    match value {
+        DxValue::None => out.push_str("null"),
+        DxValue::Boolean(v) => out.push_str(if *v { "true" } else { "false" }),
+        DxValue::String(v) => writeString(out,v),
+        DxValue::Int(v) => writeNumber(out,*v as f64)?,
+        DxValue::Int32(v) => writeNumber(out,*v as f64)?,
+        DxValue::Int64(v) => writeNumber(out,*v as f64)?,
+        DxValue::UInt64(v) => writeNumber(out,*v as f64)?,
+        DxValue::Int128(v) => writeNumber(out,*v as f64)?,
+        DxValue::UInt128(v) => writeNumber(out,*v as f64)?,
+        //取f32的最短十进制表示，0.1f32输出0.1而不是0.10000000149011612
+        DxValue::Float(v) => writeNumber(out,v.to_string().parse().unwrap_or(f64::NAN))?,
+        DxValue::Double(v) => writeNumber(out,*v)?,
+        DxValue::Decimal(v) => writeNumber(out,v.to_string().parse().map_err(|_| errValueReson::VET_TypeMismatch)?)?,
+        DxValue::RawNumber(v) => writeNumber(out,v.parse().map_err(|_| errValueReson::VET_TypeMismatch)?)?,
+        DxValue::Binary(v) => writeString(out,&base64::encode(v)),
+        DxValue::DateTime(v) => writeString(out,&v.to_string()),
+        DxValue::Raw(v) => {
+            let value = v.parse().map_err(|_| errValueReson::VET_TypeMismatch)?;
+            writeCanonical(out,&value)?;
+        },
+        DxValue::Array(arr) => {
+            out.push('[');
+            for (i,v) in arr.iter().enumerate(){
+                if i > 0{
+                    out.push(',');
+                }
+                writeCanonical(out,v)?;
+            }
+            out.push(']');
+        },
+        DxValue::Object(obj) => {
+            let mut members: Vec<&Key_Value> = obj.iter().collect();
+            //稳定排序，重复键中第一个排在前面
+            members.sort_by(|a,b| a.Key.encode_utf16().cmp(b.Key.encode_utf16()));
+            members.dedup_by(|b,a| a.Key == b.Key);
+            out.push('{');
+            for (i,kv) in members.into_iter().enumerate(){
+                if i > 0{
+                    out.push(',');
+                }
+                writeString(out,&kv.Key);
+                out.push(':');
+                writeCanonical(out,&kv.Value)?;
+            }
+            out.push('}');
+        },
+    }
+    Ok(())
+}
+
+fn writeString(out: &mut String,s: &str){
+    //写入String不会失败
+    let _ = writeJsonString(out,s);
+}
+
+/// ECMAScript Number.prototype.toString的格式：最短往返数字，
+/// 10^-7 <= |v| < 10^21 时不用指数形式
+pub fn formatNumber(v: f64) -> Result<String,errValueReson>{
+    if !v.is_finite(){
+        return Err(errValueReson::VET_NonFinite);
+    }
+    if v == 0.0{
+        return Ok("0".to_string());
+    }
+    let mut out = String::new();
+    if v < 0.0{
+        out.push('-');
+    }
+    //{:e}给出最短往返的有效数字，如 1.2345e-7
+    let sci = format!("{:e}",v.abs());
+    let (mantissa,exp) = sci.split_once('e').unwrap_or((&sci,"0"));
+    let digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+    let k = digits.len() as i32;
+    let n = exp.parse::<i32>().unwrap_or(0) + 1; //小数点在第n个数字之后
+    if k <= n && n <= 21{
+        out.push_str(&digits);
+        out.extend(std::iter::repeat_n('0',(n - k) as usize));
+    }else if 0 < n && n <= 21{
+        out.push_str(&digits[..n as usize]);
+        out.push('.');
+        out.push_str(&digits[n as usize..]);
+    }else if -6 < n && n <= 0{
+        out.push_str("0.");
+        out.extend(std::iter::repeat_n('0',-n as usize));
+        out.push_str(&digits);
+    }else{
+        out.push_str(&digits[..1]);
+        if k > 1{
+            out.push('.');
+            out.push_str(&digits[1..]);
+        }
+        let _ = write!(out,"e{}{}",if n > 0 { '+' } else { '-' },(n - 1).abs());
+    }
+    Ok(out)
+}
+
+fn writeNumber(out: &mut String,v: f64) -> Result<(),errValueReson>{
+    out.push_str(&formatNumber(v)?);
+    Ok(())
+}
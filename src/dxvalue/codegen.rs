@@ -0,0 +1,430 @@
+//! 根据JSON Schema或样本文档生成Rust结构体定义，以及和DxValue互相转换的代码。
+//! 生成的代码依赖本模块的FromDxValue/ToDxValue
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::sync::Arc;
+use crate::dxvalue::{base64, infer, DxDateTime, DxValue};
+
+/// 从DxValue转换，类型不符时返回None
+pub trait FromDxValue: Sized{
+    fn from_dxvalue(v: &DxValue) -> Option<Self>;
+}
+
+pub trait ToDxValue{
+    fn to_dxvalue(&self) -> DxValue;
+}
+
+impl FromDxValue for DxValue{
+    fn from_dxvalue(v: &DxValue) -> Option<Self>{
+        Some(v.clone())
+    }
+}
+
+impl ToDxValue for DxValue{
+    fn to_dxvalue(&self) -> DxValue{
+        self.clone()
+    }
+}
+
+impl FromDxValue for String{
+    fn from_dxvalue(v: &DxValue) -> Option<Self>{
+        match v {
+            DxValue::String(s) => Some(s.clone()),
+            DxValue::Binary(b) => Some(base64::encode(b)),
+            DxValue::DateTime(t) => Some(t.to_string()),
+            _ => None,
+        }
+    }
+}
+
+impl ToDxValue for String{
+    fn to_dxvalue(&self) -> DxValue{
+        DxValue::String(self.clone())
+    }
+}
+
+impl FromDxValue for bool{
+    fn from_dxvalue(v: &DxValue) -> Option<Self>{
+        match v {
+            DxValue::Boolean(b) => Some(*b),
+            _ => None,
+        }
+    }
+}
+
+impl ToDxValue for bool{
+    fn to_dxvalue(&self) -> DxValue{
+        DxValue::Boolean(*self)
+    }
+}
+
+fn isNumber(v: &DxValue) -> bool{
+    matches!(v,DxValue::Int(_) | DxValue::Int32(_) | DxValue::Int64(_) | DxValue::UInt64(_) | DxValue::Int128(_) | DxValue::UInt128(_)
+        | DxValue::Float(_) | DxValue::Double(_) | DxValue::Decimal(_) | DxValue::RawNumber(_))
+}
+
+macro_rules! integerConvert {
+    ($($t:ty => $variant:ident),*) => {
+        $(
+            impl FromDxValue for $t{
+                fn from_dxvalue(v: &DxValue) -> Option<Self>{
+                    if isNumber(v) { v.try_as_integer().ok() } else { None }
+                }
+            }
+
+            impl ToDxValue for $t{
+                fn to_dxvalue(&self) -> DxValue{
+                    DxValue::$variant(*self)
+                }
+            }
+        )*
+    };
+}
+
+integerConvert!(i32 => Int32,i64 => Int64,u64 => UInt64,i128 => Int128,u128 => UInt128);
+
+impl FromDxValue for f64{
+    fn from_dxvalue(v: &DxValue) -> Option<Self>{
+        if isNumber(v) { Some(v.as_double()) } else { None }
+    }
+}
+
+impl ToDxValue for f64{
+    fn to_dxvalue(&self) -> DxValue{
+        DxValue::Double(*self)
+    }
+}
+
+impl FromDxValue for DxDateTime{
+    fn from_dxvalue(v: &DxValue) -> Option<Self>{
+        match v {
+            DxValue::DateTime(t) => Some(*t),
+            DxValue::String(s) => DxDateTime::parse_rfc3339(s),
+            _ => None,
+        }
+    }
+}
+
+impl ToDxValue for DxDateTime{
+    fn to_dxvalue(&self) -> DxValue{
+        DxValue::DateTime(*self)
+    }
+}
+
+/// null为None
+impl<T: FromDxValue> FromDxValue for Option<T>{
+    fn from_dxvalue(v: &DxValue) -> Option<Self>{
+        match v {
+            DxValue::None => Some(None),
+            _ => T::from_dxvalue(v).map(Some),
+        }
+    }
+}
+
+impl<T: ToDxValue> ToDxValue for Option<T>{
+    fn to_dxvalue(&self) -> DxValue{
+        self.as_ref().map_or(DxValue::None,|v| v.to_dxvalue())
+    }
+}
+
+/// 递归引用自身的字段
+impl<T: FromDxValue> FromDxValue for Box<T>{
+    fn from_dxvalue(v: &DxValue) -> Option<Self>{
+        T::from_dxvalue(v).map(Box::new)
+    }
+}
+
+impl<T: ToDxValue> ToDxValue for Box<T>{
+    fn to_dxvalue(&self) -> DxValue{
+        self.as_ref().to_dxvalue()
+    }
+}
+
+impl<T: FromDxValue> FromDxValue for Vec<T>{
+    fn from_dxvalue(v: &DxValue) -> Option<Self>{
+        match v {
+            DxValue::Array(arr) => arr.iter().map(T::from_dxvalue).collect(),
+            DxValue::Raw(raw) => Self::from_dxvalue(raw.value()?),
+            _ => None,
+        }
+    }
+}
+
+impl<T: ToDxValue> ToDxValue for Vec<T>{
+    fn to_dxvalue(&self) -> DxValue{
+        DxValue::Array(Arc::new(self.iter().map(|v| v.to_dxvalue()).collect()))
+    }
+}
+
+#[derive(Debug,Clone)]
+pub struct CodegenOptions{
+    /// 根结构体的名字
+    pub root_name: String,
+    pub derives: Vec<String>,
+    /// 生成代码中引用本库的路径
+    pub crate_path: String,
+}
+
+impl Default for CodegenOptions{
+    fn default() -> Self{
+        CodegenOptions{
+            root_name: "Root".to_string(),
+            derives: vec!["Debug".to_string(),"Clone".to_string()],
+            crate_path: "rvalue".to_string(),
+        }
+    }
+}
+
+/// 从样本推断schema后生成代码
+pub fn rustFromSamples(samples: &[DxValue],opts: &CodegenOptions) -> String{
+    rustFromSchema(&infer::inferSchema(samples),opts)
+}
+
+/// 有properties的object生成结构体，type带null或不在required中的字段为Option，
+/// $ref引用的$defs按定义名生成一次，直接(不经过Vec)引用自身的字段用Box；
+/// 无法用单一类型表示的联合类型使用DxValue；根不是以root_name命名的结构体时(如数组)，
+/// 在名字没有被占用的情况下生成同名的类型别名
+pub fn rustFromSchema(schema: &DxValue,opts: &CodegenOptions) -> String{
+    //生成的代码中用到的类型名不能再用作结构体名
+    let names = RESERVED_TYPES.iter().map(|n| n.to_string()).collect();
+    let mut gen = Generator{root: schema,structs: Vec::new(),names,refs: HashMap::new(),building: Vec::new(),derives: opts.derives.join(", ")};
+    let root = gen.typeOf(schema,&opts.root_name);
+    let mut out = String::new();
+    let usesDateTime = root.contains("DxDateTime") || gen.structs.iter().any(|s| s.contains("DxDateTime"));
+    let _ = writeln!(out,"use {}::dxvalue::{};",opts.crate_path,if usesDateTime { "{DxDateTime, DxValue}" } else { "DxValue" });
+    let _ = writeln!(out,"use {}::dxvalue::codegen::{{FromDxValue, ToDxValue}};",opts.crate_path);
+    let alias = pascalName(&opts.root_name);
+    if root != alias && gen.names.insert(alias.clone()){
+        let _ = write!(out,"\npub type {} = {};\n",alias,root);
+    }
+    //内层结构体先生成完，倒过来让根结构体排在最前面
+    for code in gen.structs.iter().rev(){
+        out.push('\n');
+        out.push_str(code);
+    }
+    out
+}
+
+struct Field{
+    json: String,
+    ident: String,
+    ty: String,
+    required: bool,
+}
+
+struct Generator<'s>{
+    root: &'s DxValue,
+    structs: Vec<String>,
+    names: HashSet<String>,
+    refs: HashMap<String,String>,   //$ref到已生成的类型
+    building: Vec<String>,          //正在生成的结构体，字段引用它们时需要Box
+    derives: String,
+}
+
+const RESERVED_TYPES: [&str; 9] = ["Option","Vec","Box","String","DxValue","DxDateTime","FromDxValue","ToDxValue","Self"];
+
+impl Generator<'_>{
+    fn typeOf(&mut self,schema: &DxValue,hint: &str) -> String{
+        if let Some(DxValue::String(r)) = schema.get_byName("$ref"){
+            return self.refType(r);
+        }
+        let mut types: Vec<&str> = match schema.get_byName("type") {
+            Some(DxValue::String(t)) => vec![t.as_str()],
+            Some(DxValue::Array(ts)) => ts.iter().filter_map(|t| if let DxValue::String(t) = t { Some(t.as_str()) } else { None }).collect(),
+            _ if schema.get_byName("properties").is_some() => vec!["object"],
+            _ => Vec::new(),
+        };
+        let nullable = types.contains(&"null");
+        types.retain(|t| *t != "null");
+        let format = match schema.get_byName("format") {
+            Some(DxValue::String(f)) => f.as_str(),
+            _ => "",
+        };
+        let ty = match types.as_slice() {
+            ["object"] if schema.get_byName("properties").is_some() => self.structType(schema,hint),
+            ["array"] => match schema.get_byName("items") {
+                Some(items) => format!("Vec<{}>",self.typeOf(items,&elementName(hint))),
+                None => "Vec<DxValue>".to_string(),
+            },
+            ["string"] if format == "date-time" => "DxDateTime".to_string(),
+            ["string"] => "String".to_string(),
+            ["integer"] => match format {
+                "int32" => "i32",
+                "uint64" => "u64",
+                "int128" => "i128",
+                "uint128" => "u128",
+                _ => "i64",
+            }.to_string(),
+            ["number"] => "f64".to_string(),
+            ["boolean"] => "bool".to_string(),
+            _ => return "DxValue".to_string(),
+        };
+        if nullable { format!("Option<{}>",ty) } else { ty }
+    }
+
+    fn refType(&mut self,r: &str) -> String{
+        if let Some(ty) = self.refs.get(r){
+            return ty.clone();
+        }
+        let target = r.strip_prefix('#').and_then(|p| self.root.get_byPointer(p));
+        let target = match target {
+            Some(t) => t,
+            None => return "DxValue".to_string(),
+        };
+        //先登记类型，递归引用时直接使用；不是结构体的定义递归引用自身时只能用DxValue
+        let name = r.rsplit('/').next().unwrap_or("Ref");
+        if target.get_byName("properties").is_none(){
+            self.refs.insert(r.to_string(),"DxValue".to_string());
+            let ty = self.typeOf(target,name);
+            self.refs.insert(r.to_string(),ty.clone());
+            return ty;
+        }
+        let ty = self.uniqueName(name);
+        self.refs.insert(r.to_string(),ty.clone());
+        self.writeStruct(target,&ty);
+        ty
+    }
+
+    fn uniqueName(&mut self,hint: &str) -> String{
+        let base = pascalName(hint);
+        let mut name = base.clone();
+        let mut i = 2;
+        while !self.names.insert(name.clone()){
+            name = format!("{}{}",base,i);
+            i += 1;
+        }
+        name
+    }
+
+    fn structType(&mut self,schema: &DxValue,hint: &str) -> String{
+        let name = self.uniqueName(hint);
+        self.writeStruct(schema,&name);
+        name
+    }
+
+    fn writeStruct(&mut self,schema: &DxValue,name: &str){
+        self.building.push(name.to_string());
+        let required: Vec<&str> = match schema.get_byName("required") {
+            Some(DxValue::Array(names)) => names.iter().filter_map(|n| if let DxValue::String(n) = n { Some(n.as_str()) } else { None }).collect(),
+            _ => Vec::new(),
+        };
+        let mut idents = HashSet::new();
+        let mut fields = Vec::new();
+        if let Some(DxValue::Object(props)) = schema.get_byName("properties"){
+            for kv in props.iter(){
+                let isRequired = required.contains(&kv.Key.as_str());
+                let mut ty = self.typeOf(&kv.Value,&kv.Key);
+                if self.building.contains(&ty){
+                    ty = format!("Box<{}>",ty);
+                }else if let Some(inner) = ty.strip_prefix("Option<").and_then(|t| t.strip_suffix('>')).filter(|t| self.building.iter().any(|b| b == t)){
+                    ty = format!("Option<Box<{}>>",inner);
+                }
+                if !isRequired && !ty.starts_with("Option<"){
+                    ty = format!("Option<{}>",ty);
+                }
+                let mut ident = fieldName(&kv.Key);
+                while !idents.insert(ident.clone()){
+                    ident.push('_');
+                }
+                fields.push(Field{json: kv.Key.clone(),ident,ty,required: isRequired});
+            }
+        }
+        self.building.pop();
+        let mut code = String::new();
+        let _ = writeln!(code,"#[derive({})]",self.derives);
+        let _ = writeln!(code,"pub struct {} {{",name);
+        for f in &fields{
+            if f.ident.trim_start_matches("r#") != f.json{
+                let _ = writeln!(code,"    /// json: {:?}",f.json);
+            }
+            let _ = writeln!(code,"    pub {}: {},",f.ident,f.ty);
+        }
+        code.push_str("}\n\n");
+        let _ = writeln!(code,"impl FromDxValue for {} {{",name);
+        code.push_str("    fn from_dxvalue(v: &DxValue) -> Option<Self> {\n");
+        //lazy_depth解析出的延迟节点先取解析后的值
+        code.push_str("        let v = match v {\n            DxValue::Raw(raw) => raw.value()?,\n            v => v,\n        };\n");
+        code.push_str("        if !matches!(v, DxValue::Object(_)) {\n            return None;\n        }\n");
+        let _ = writeln!(code,"        Some({} {{",name);
+        for f in &fields{
+            if f.required{
+                let _ = writeln!(code,"            {}: FromDxValue::from_dxvalue(v.get_byName({:?})?)?,",f.ident,f.json);
+            }else{
+                let _ = writeln!(code,"            {}: match v.get_byName({:?}) {{ Some(x) => FromDxValue::from_dxvalue(x)?, None => None }},",f.ident,f.json);
+            }
+        }
+        code.push_str("        })\n    }\n}\n\n");
+        let _ = writeln!(code,"impl ToDxValue for {} {{",name);
+        code.push_str("    fn to_dxvalue(&self) -> DxValue {\n        let mut v = DxValue::newObject();\n");
+        for f in &fields{
+            if f.required{
+                let _ = writeln!(code,"        v.setKeyValue({:?}, self.{}.to_dxvalue());",f.json,f.ident);
+            }else{
+                let _ = writeln!(code,"        if let Some(x) = &self.{} {{\n            v.setKeyValue({:?}, x.to_dxvalue());\n        }}",f.ident,f.json);
+            }
+        }
+        code.push_str("        v\n    }\n}\n");
+        self.structs.push(code);
+    }
+}
+
+/// 按非字母数字和小写到大写的边界切分
+fn words(s: &str) -> Vec<String>{
+    let mut out: Vec<String> = Vec::new();
+    let mut prevLower = false;
+    for c in s.chars(){
+        if !c.is_alphanumeric(){
+            prevLower = false;
+            out.push(String::new());
+            continue;
+        }
+        if out.is_empty() || (c.is_uppercase() && prevLower){
+            out.push(String::new());
+        }
+        prevLower = c.is_lowercase() || c.is_ascii_digit();
+        if let Some(w) = out.last_mut(){
+            w.push(c);
+        }
+    }
+    out.retain(|w| !w.is_empty());
+    out
+}
+
+/// 数组元素的类型名，复数形式的tags取Tag，否则加Item
+fn elementName(hint: &str) -> String{
+    match hint.strip_suffix('s') {
+        Some(single) if single.len() > 1 && !single.ends_with('s') => single.to_string(),
+        _ => format!("{}Item",hint),
+    }
+}
+
+fn pascalName(s: &str) -> String{
+    let mut name: String = words(s).iter().map(|w| {
+        let mut cs = w.chars();
+        cs.next().map_or(String::new(),|c| c.to_uppercase().chain(cs).collect())
+    }).collect();
+    if !name.starts_with(|c: char| c.is_alphabetic()){
+        name.insert(0,'T');
+    }
+    name
+}
+
+//包括保留给将来使用的关键字
+const KEYWORDS: [&str; 48] = ["as","async","await","break","const","continue","dyn","else","enum","extern","false","fn","for",
+    "if","impl","in","let","loop","match","mod","move","mut","pub","ref","return","static","struct","trait","true","type",
+    "unsafe","use","where","while","abstract","become","box","do","final","gen","macro","override","priv","try","typeof",
+    "unsized","virtual","yield"];
+
+fn fieldName(s: &str) -> String{
+    let mut name = words(s).iter().map(|w| w.to_lowercase()).collect::<Vec<_>>().join("_");
+    if name.is_empty() || !name.starts_with(|c: char| c.is_alphabetic()){
+        name.insert_str(0,"field_");
+    }
+    match name.as_str() {
+        "self" | "super" | "crate" => name.push('_'),
+        n if KEYWORDS.contains(&n) => name.insert_str(0,"r#"),
+        _ => {},
+    }
+    name
+}
@@ -0,0 +1,250 @@
+//! 日期时间值，内部保存UTC时间戳(秒+纳秒)和时区偏移，
+//! 支持RFC 3339文本、Unix时间戳、BSON UTC datetime以及msgpack时间戳扩展(-1)之间的转换
+
+use std::fmt::{Display, Formatter, Result};
+
+/// msgpack时间戳扩展的类型码
+pub const MSGPACK_TIMESTAMP_EXT: i8 = -1;
+
+const NANOS_PER_SEC: i64 = 1_000_000_000;
+
+/// RFC 3339允许的最大时区偏移(分钟)，即23:59
+pub const MAX_OFFSET_MINUTES: i16 = 23 * 60 + 59;
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Hash)]
+pub struct DxDateTime{
+    secs: i64,          //UTC Unix秒
+    nanos: u32,         //秒内纳秒 0..1e9
+    offset: i16,        //时区偏移(分钟，东区为正)，只影响文本表示
+}
+
+// 公历日期与1970-01-01之间的天数转换(Howard Hinnant算法)
+fn days_from_civil(y: i64,m: u32,d: u32) -> i64{
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let yoe = y - era * 400;
+    let mp = (m as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + d as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+fn civil_from_days(z: i64) -> (i64,u32,u32){
+    let z = z + 719468;
+    let era = if z >= 0 { z } else { z - 146096 } / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    (if m <= 2 { yoe + era * 400 + 1 } else { yoe + era * 400 },m,d)
+}
+
+fn days_in_month(y: i64,m: u32) -> u32{
+    match m {
+        2 if (y % 4 == 0 && y % 100 != 0) || y % 400 == 0 => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+impl DxDateTime{
+    /// 由UTC Unix秒和秒内纳秒构造，nanos超过1秒的部分会进位
+    pub fn new(secs: i64,nanos: u32) -> Self{
+        DxDateTime{
+            secs: secs.saturating_add(nanos as i64 / NANOS_PER_SEC),
+            nanos: nanos % NANOS_PER_SEC as u32,
+            offset: 0,
+        }
+    }
+
+    pub fn from_unix_secs(secs: i64) -> Self{
+        DxDateTime::new(secs,0)
+    }
+
+    pub fn from_unix_millis(millis: i64) -> Self{
+        DxDateTime::new(millis.div_euclid(1000),(millis.rem_euclid(1000) * 1_000_000) as u32)
+    }
+
+    pub fn from_unix_nanos(nanos: i128) -> Self{
+        let n = NANOS_PER_SEC as i128;
+        DxDateTime::new(nanos.div_euclid(n) as i64,nanos.rem_euclid(n) as u32)
+    }
+
+    /// 设置文本表示使用的时区偏移(分钟)，不改变所表示的时刻；超出±23:59时返回None
+    pub fn with_offset(mut self,offset_minutes: i16) -> Option<Self>{
+        if !(-MAX_OFFSET_MINUTES..=MAX_OFFSET_MINUTES).contains(&offset_minutes){
+            return None;
+        }
+        self.offset = offset_minutes;
+        Some(self)
+    }
+
+    pub fn offset_minutes(&self) -> i16{
+        self.offset
+    }
+
+    pub fn unix_secs(&self) -> i64{
+        self.secs
+    }
+
+    pub fn subsec_nanos(&self) -> u32{
+        self.nanos
+    }
+
+    /// 超出i64毫秒范围(约±2.9亿年)时取i64::MIN/MAX
+    pub fn unix_millis(&self) -> i64{
+        self.secs.saturating_mul(1000).saturating_add((self.nanos / 1_000_000) as i64)
+    }
+
+    pub fn unix_nanos(&self) -> i128{
+        self.secs as i128 * NANOS_PER_SEC as i128 + self.nanos as i128
+    }
+
+    /// 解析RFC 3339时间，如 2006-01-02T15:04:05.999+08:00
+    pub fn parse_rfc3339(text: &str) -> Option<Self>{
+        let b = text.as_bytes();
+        if b.len() < 20{
+            return None;
+        }
+        fn num(b: &[u8]) -> Option<u32>{
+            let mut n = 0u32;
+            for &c in b{
+                if !c.is_ascii_digit(){
+                    return None;
+                }
+                n = n * 10 + (c - b'0') as u32;
+            }
+            Some(n)
+        }
+        if b[4] != b'-' || b[7] != b'-' || b[13] != b':' || b[16] != b':'{
+            return None;
+        }
+        if b[10] != b'T' && b[10] != b't' && b[10] != b' '{
+            return None;
+        }
+        let year = num(&b[0..4])? as i64;
+        let month = num(&b[5..7])?;
+        let day = num(&b[8..10])?;
+        let hour = num(&b[11..13])?;
+        let minute = num(&b[14..16])?;
+        let second = num(&b[17..19])?;
+        if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year,month)
+            || hour > 23 || minute > 59 || second > 60{
+            return None;
+        }
+        let mut i = 19;
+        let mut nanos = 0u32;
+        if b[i] == b'.'{
+            i += 1;
+            let start = i;
+            while i < b.len() && b[i].is_ascii_digit(){
+                if i - start < 9{
+                    nanos = nanos * 10 + (b[i] - b'0') as u32;
+                }
+                i += 1;
+            }
+            if i == start{
+                return None;
+            }
+            for _ in (i - start)..9{
+                nanos *= 10;
+            }
+        }
+        let offset: i64 = match b.get(i) {
+            Some(b'Z') | Some(b'z') if i + 1 == b.len() => 0,
+            Some(&sign) if (sign == b'+' || sign == b'-') && i + 6 == b.len() && b[i + 3] == b':' => {
+                let oh = num(&b[i + 1..i + 3])?;
+                let om = num(&b[i + 4..i + 6])?;
+                if oh > 23 || om > 59{
+                    return None;
+                }
+                let v = (oh * 60 + om) as i64;
+                if sign == b'-' { -v } else { v }
+            },
+            _ => return None,
+        };
+        let days = days_from_civil(year,month,day);
+        let local = days * 86400 + (hour * 3600 + minute * 60 + second) as i64;
+        Some(DxDateTime{secs: local - offset * 60,nanos,offset: offset as i16})
+    }
+
+    /// 按RFC 3339格式输出，小数秒按毫秒/微秒/纳秒精度取最短表示
+    pub fn to_rfc3339(&self) -> String{
+        let local = self.secs.saturating_add(self.offset as i64 * 60);
+        let (y,m,d) = civil_from_days(local.div_euclid(86400));
+        let sod = local.rem_euclid(86400);
+        let mut s = format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",y,m,d,sod / 3600,sod % 3600 / 60,sod % 60);
+        if self.nanos != 0{
+            let frac = format!("{:09}",self.nanos);
+            let frac = if frac.ends_with("000000") { &frac[..3] } else if frac.ends_with("000") { &frac[..6] } else { &frac[..] };
+            s.push('.');
+            s.push_str(frac);
+        }
+        if self.offset == 0{
+            s.push('Z');
+        }else{
+            let sign = if self.offset < 0 { '-' } else { '+' };
+            let o = self.offset.unsigned_abs();
+            s.push_str(&format!("{}{:02}:{:02}",sign,o / 60,o % 60));
+        }
+        s
+    }
+
+    /// BSON的UTC datetime：Unix毫秒，超出范围时与unix_millis一样取极值
+    pub fn to_bson_datetime(&self) -> i64{
+        self.unix_millis()
+    }
+
+    pub fn from_bson_datetime(millis: i64) -> Self{
+        DxDateTime::from_unix_millis(millis)
+    }
+
+    /// msgpack时间戳扩展(-1)的数据部分，按范围选择32/64/96位格式
+    pub fn to_msgpack_timestamp(&self) -> Vec<u8>{
+        if self.secs >> 34 == 0{
+            let data = ((self.nanos as u64) << 34) | self.secs as u64;
+            if data >> 32 == 0{
+                return (data as u32).to_be_bytes().to_vec();
+            }
+            return data.to_be_bytes().to_vec();
+        }
+        let mut out = self.nanos.to_be_bytes().to_vec();
+        out.extend_from_slice(&self.secs.to_be_bytes());
+        out
+    }
+
+    pub fn from_msgpack_timestamp(data: &[u8]) -> Option<Self>{
+        match data.len() {
+            4 => Some(DxDateTime::from_unix_secs(u32::from_be_bytes([data[0],data[1],data[2],data[3]]) as i64)),
+            8 => {
+                let mut b = [0u8;8];
+                b.copy_from_slice(data);
+                let v = u64::from_be_bytes(b);
+                let nanos = (v >> 34) as u32;
+                if nanos >= NANOS_PER_SEC as u32{
+                    return None;
+                }
+                Some(DxDateTime::new((v & 0x3_FFFF_FFFF) as i64,nanos))
+            },
+            12 => {
+                let nanos = u32::from_be_bytes([data[0],data[1],data[2],data[3]]);
+                if nanos >= NANOS_PER_SEC as u32{
+                    return None;
+                }
+                let mut b = [0u8;8];
+                b.copy_from_slice(&data[4..]);
+                Some(DxDateTime::new(i64::from_be_bytes(b),nanos))
+            },
+            _ => None,
+        }
+    }
+}
+
+impl Display for DxDateTime{
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        f.write_str(&self.to_rfc3339())
+    }
+}
@@ -0,0 +1,282 @@
+//! 十进制定点数，值为 coeff × 10^exp，用于金额等不能有二进制舍入误差的场景。
+//! 系数使用i128，最多38位有效数字，足以覆盖BSON Decimal128的34位
+
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter, Result};
+use std::str::FromStr;
+
+const POW10: [i128;39] = {
+    let mut t = [1i128;39];
+    let mut i = 1;
+    while i < 39{
+        t[i] = t[i - 1] * 10;
+        i += 1;
+    }
+    t
+};
+
+const DEC128_BIAS: i32 = 6176;
+const DEC128_MAX_COEFF: u128 = 9_999_999_999_999_999_999_999_999_999_999_999;
+
+/// 精确的十进制数，但不是任意精度：系数是i128，最多38位有效数字，指数为i32。
+/// 超出时解析返回Err、运算返回None，不会静默舍入
+#[derive(Debug,Clone,Copy)]
+pub struct DxDecimal{
+    coeff: i128,
+    exp: i32,
+}
+
+impl DxDecimal{
+    pub fn new(coeff: i128,exp: i32) -> Self{
+        DxDecimal{coeff,exp}
+    }
+
+    pub fn from_i128(v: i128) -> Self{
+        DxDecimal{coeff: v,exp: 0}
+    }
+
+    pub fn coeff(&self) -> i128{
+        self.coeff
+    }
+
+    pub fn exp(&self) -> i32{
+        self.exp
+    }
+
+    /// 小数位数，没有小数部分时为0
+    pub fn scale(&self) -> u32{
+        if self.exp < 0 { self.exp.unsigned_abs() } else { 0 }
+    }
+
+    pub fn is_zero(&self) -> bool{
+        self.coeff == 0
+    }
+
+    /// 显式转换为f64，可能丢失精度(最接近的二进制浮点数)
+    pub fn to_f64(&self) -> f64{
+        self.to_string().parse::<f64>().unwrap_or(f64::NAN)
+    }
+
+    /// 由f64转换，使用能精确还原该浮点数的最短十进制表示
+    pub fn from_f64(v: f64) -> Option<Self>{
+        if !v.is_finite(){
+            return None;
+        }
+        format!("{:e}",v).parse().ok()
+    }
+
+    /// 整数部分正好可以表示时返回Some，有非零小数时返回None
+    pub fn to_i128(&self) -> Option<i128>{
+        if self.exp >= 0{
+            return self.coeff.checked_mul(*POW10.get(self.exp as usize)?);
+        }
+        let p = *POW10.get(self.exp.unsigned_abs() as usize)?;
+        if self.coeff % p != 0{
+            return None;
+        }
+        Some(self.coeff / p)
+    }
+
+    /// 调整到指定的指数，只能放大系数(不丢精度)，溢出返回None
+    fn rescale(&self,exp: i32) -> Option<i128>{
+        if exp > self.exp{
+            return None;
+        }
+        self.coeff.checked_mul(*POW10.get((self.exp - exp) as usize)?)
+    }
+
+    fn align(a: &Self,b: &Self) -> Option<(i128,i128,i32)>{
+        let exp = a.exp.min(b.exp);
+        Some((a.rescale(exp)?,b.rescale(exp)?,exp))
+    }
+
+    pub fn checked_add(&self,other: &Self) -> Option<Self>{
+        let (a,b,exp) = DxDecimal::align(self,other)?;
+        Some(DxDecimal{coeff: a.checked_add(b)?,exp})
+    }
+
+    pub fn checked_sub(&self,other: &Self) -> Option<Self>{
+        let (a,b,exp) = DxDecimal::align(self,other)?;
+        Some(DxDecimal{coeff: a.checked_sub(b)?,exp})
+    }
+
+    pub fn checked_mul(&self,other: &Self) -> Option<Self>{
+        Some(DxDecimal{coeff: self.coeff.checked_mul(other.coeff)?,exp: self.exp.checked_add(other.exp)?})
+    }
+
+    pub fn checked_neg(&self) -> Option<Self>{
+        Some(DxDecimal{coeff: self.coeff.checked_neg()?,exp: self.exp})
+    }
+
+    /// 保留dp位小数，四舍五入(0.5远离0)，如 1.005 -> 1.01
+    pub fn round_dp(&self,dp: u32) -> Option<Self>{
+        let target = -(dp as i32);
+        if self.exp >= target{
+            return Some(DxDecimal{coeff: self.rescale(target)?,exp: target});
+        }
+        //舍去超过38位时系数的绝对值一定小于舍去单位的一半
+        let p = match POW10.get((target - self.exp) as usize) {
+            Some(p) => *p,
+            None => return Some(DxDecimal{coeff: 0,exp: target}),
+        };
+        let q = self.coeff / p;
+        let r = self.coeff % p;
+        //r*2可能溢出，改为与p-r比较
+        let q = if r.unsigned_abs() >= p as u128 - r.unsigned_abs() { q + self.coeff.signum() } else { q };
+        Some(DxDecimal{coeff: q,exp: target})
+    }
+
+    /// BSON Decimal128(IEEE 754-2008 BID)编码，小端序；超出34位有效数字或指数范围时返回None
+    pub fn to_decimal128_bytes(&self) -> Option<[u8;16]>{
+        let mut coeff = self.coeff.unsigned_abs();
+        let mut exp = self.exp;
+        //末尾的0移到指数上，35~38位但末尾有0的系数仍可精确表示
+        while coeff != 0 && coeff.is_multiple_of(10) && (coeff > DEC128_MAX_COEFF || exp < -DEC128_BIAS){
+            coeff /= 10;
+            exp += 1;
+        }
+        if coeff == 0{
+            exp = exp.clamp(-DEC128_BIAS,DEC128_BIAS - 65);
+        }
+        //指数过大时尝试补零到系数上
+        while exp > DEC128_BIAS - 65 && coeff.checked_mul(10).is_some_and(|c| c <= DEC128_MAX_COEFF){
+            coeff *= 10;
+            exp -= 1;
+        }
+        if coeff > DEC128_MAX_COEFF || !(-DEC128_BIAS..=DEC128_BIAS - 65).contains(&exp){
+            return None;
+        }
+        let mut bits = coeff | (((exp + DEC128_BIAS) as u128) << 113);
+        if self.coeff < 0{
+            bits |= 1u128 << 127;
+        }
+        Some(bits.to_le_bytes())
+    }
+
+    /// 从BSON Decimal128解码，Infinity/NaN无法表示，返回None
+    pub fn from_decimal128_bytes(b: [u8;16]) -> Option<Self>{
+        let bits = u128::from_le_bytes(b);
+        let negative = bits >> 127 == 1;
+        if (bits >> 122) & 0x1E == 0x1E{
+            return None;
+        }
+        let (exp,coeff) = if (bits >> 125) & 0x3 == 0x3{
+            //系数高位为100的形式，按规范值必然超过10^34，视为0
+            (((bits >> 111) & 0x3FFF) as i32,0u128)
+        }else{
+            (((bits >> 113) & 0x3FFF) as i32,bits & ((1u128 << 113) - 1))
+        };
+        let coeff = if coeff > DEC128_MAX_COEFF { 0 } else { coeff as i128 };
+        Some(DxDecimal{coeff: if negative { -coeff } else { coeff },exp: exp - DEC128_BIAS})
+    }
+}
+
+impl FromStr for DxDecimal{
+    type Err = ();
+
+    /// 解析十进制文本，如 -12.50、1e400，保留末尾的0(1.10的scale为2)
+    fn from_str(s: &str) -> std::result::Result<Self,()>{
+        let b = s.as_bytes();
+        let mut i = 0;
+        let negative = match b.first() {
+            Some(b'-') => { i += 1; true },
+            Some(b'+') => { i += 1; false },
+            _ => false,
+        };
+        let mut coeff: i128 = 0;
+        let mut exp: i64 = 0;
+        let mut digits = 0;
+        let mut seen_point = false;
+        while i < b.len(){
+            match b[i] {
+                c @ b'0'..=b'9' => {
+                    coeff = coeff.checked_mul(10).and_then(|v| v.checked_add((c - b'0') as i128)).ok_or(())?;
+                    digits += 1;
+                    if seen_point{
+                        exp -= 1;
+                    }
+                },
+                b'.' if !seen_point => seen_point = true,
+                b'e' | b'E' => break,
+                _ => return Err(()),
+            }
+            i += 1;
+        }
+        if digits == 0{
+            return Err(());
+        }
+        if i < b.len(){
+            let e = s[i + 1..].parse::<i64>().map_err(|_| ())?;
+            exp = exp.checked_add(e).ok_or(())?;
+        }
+        if exp < i32::MIN as i64 || exp > i32::MAX as i64{
+            return Err(());
+        }
+        Ok(DxDecimal{coeff: if negative { -coeff } else { coeff },exp: exp as i32})
+    }
+}
+
+impl Display for DxDecimal{
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let digits = self.coeff.unsigned_abs().to_string();
+        if self.coeff < 0{
+            f.write_str("-")?;
+        }
+        if self.exp >= 0{
+            if self.exp > 20{
+                return write!(f,"{}e{}",digits,self.exp);
+            }
+            f.write_str(&digits)?;
+            for _ in 0..self.exp{
+                f.write_str("0")?;
+            }
+            return Ok(());
+        }
+        let scale = self.exp.unsigned_abs() as usize;
+        if scale > digits.len() + 20{
+            return write!(f,"{}e{}",digits,self.exp);
+        }
+        if scale >= digits.len(){
+            f.write_str("0.")?;
+            for _ in 0..scale - digits.len(){
+                f.write_str("0")?;
+            }
+            return f.write_str(&digits);
+        }
+        let (int_part,frac_part) = digits.split_at(digits.len() - scale);
+        write!(f,"{}.{}",int_part,frac_part)
+    }
+}
+
+impl PartialEq for DxDecimal{
+    /// 按数值比较，1.10 == 1.1
+    fn eq(&self,other: &Self) -> bool{
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for DxDecimal{}
+
+impl PartialOrd for DxDecimal{
+    fn partial_cmp(&self,other: &Self) -> Option<Ordering>{
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for DxDecimal{
+    fn cmp(&self,other: &Self) -> Ordering{
+        match DxDecimal::align(self,other) {
+            Some((a,b,_)) => a.cmp(&b),
+            //对齐溢出说明指数相差很大，先比符号再比数量级
+            None => {
+                let sign = self.coeff.signum().cmp(&other.coeff.signum());
+                if sign != Ordering::Equal || self.coeff == 0{
+                    return sign;
+                }
+                let mag = |d: &DxDecimal| d.coeff.unsigned_abs().to_string().len() as i64 + d.exp as i64;
+                let ord = mag(self).cmp(&mag(other));
+                if self.coeff < 0 { ord.reverse() } else { ord }
+            },
+        }
+    }
+}
@@ -0,0 +1,225 @@
+//! 两棵DxValue树的结构化比较，结果是带JSON Pointer路径的变更列表，可以输出为统一diff风格的文本
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter, Write};
+use crate::dxvalue::schema::{exactInteger, exactNumber, jsonEqual, numberOf, typeName};
+use crate::dxvalue::{escapePointerToken, json, DxValue, Key_Value};
+
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum ChangeKind{
+    Added,
+    Removed,
+    Changed,
+    /// 值的JSON类型变了，如数字变成字符串
+    TypeChanged,
+}
+
+#[derive(Debug,Clone)]
+pub struct Change{
+    pub path: String,
+    pub kind: ChangeKind,
+    /// Added时为None
+    pub old: Option<DxValue>,
+    /// Removed时为None
+    pub new: Option<DxValue>,
+}
+
+/// 数组的比较方式
+#[derive(Debug,Clone,PartialEq)]
+pub enum ArrayMode{
+    /// 按下标逐个比较
+    Ordered,
+    /// 元素都是带这个键的对象时按键值配对，否则按下标比较。
+    /// 配对时不考虑顺序，路径中是旧数组的下标，新增的元素路径为 -(数组末尾)
+    ByKey(String),
+}
+
+#[derive(Debug,Clone)]
+pub struct DiffOptions{
+    pub arrays: ArrayMode,
+    /// 指定路径下的数组按键配对，优先于arrays，如 ("/servers","name")
+    pub array_keys: Vec<(String,String)>,
+    /// 两个数字之差的绝对值不超过它时视为相等
+    pub tolerance: f64,
+}
+
+impl Default for DiffOptions{
+    fn default() -> Self{
+        DiffOptions{arrays: ArrayMode::Ordered,array_keys: Vec::new(),tolerance: 0.0}
+    }
+}
+
+/// integer和number是同一种类型
+fn kindOf(v: &DxValue) -> &'static str{
+    match typeName(v) {
+        "integer" => "number",
+        t => t,
+    }
+}
+
+fn numberEqual(a: &DxValue,b: &DxValue,tolerance: f64) -> bool{
+    if let (Some(x),Some(y)) = (exactInteger(a),exactInteger(b)){
+        if tolerance == 0.0{
+            return x == y;
+        }
+    }
+    match (numberOf(a),numberOf(b)) {
+        (Some(x),Some(y)) => x == y || (x - y).abs() <= tolerance,
+        _ => false,
+    }
+}
+
+struct Differ<'o>{
+    opts: &'o DiffOptions,
+    changes: Vec<Change>,
+}
+
+impl Differ<'_>{
+    fn push(&mut self,path: &str,kind: ChangeKind,old: Option<&DxValue>,new: Option<&DxValue>){
+        self.changes.push(Change{path: path.to_string(),kind,old: old.cloned(),new: new.cloned()});
+    }
+
+    fn diff(&mut self,path: &str,a: &DxValue,b: &DxValue){
+        let (a,b) = (a.loaded(),b.loaded());
+        let (ka,kb) = (kindOf(a),kindOf(b));
+        if ka != kb{
+            self.push(path,ChangeKind::TypeChanged,Some(a),Some(b));
+            return;
+        }
+        match (a,b) {
+            (DxValue::Object(x),DxValue::Object(y)) => self.diffObject(path,x,y),
+            (DxValue::Array(x),DxValue::Array(y)) => self.diffArray(path,x,y),
+            _ if ka == "number" => {
+                if !numberEqual(a,b,self.opts.tolerance){
+                    self.push(path,ChangeKind::Changed,Some(a),Some(b));
+                }
+            },
+            _ => {
+                if !jsonEqual(a,b){
+                    self.push(path,ChangeKind::Changed,Some(a),Some(b));
+                }
+            },
+        }
+    }
+
+    /// 重复的键只比较第一个，与get_byName一致
+    fn diffObject(&mut self,path: &str,a: &[Key_Value],b: &[Key_Value]){
+        let find = |m: &[Key_Value],key: &str| m.iter().position(|kv| kv.Key == key);
+        for (i,kv) in a.iter().enumerate(){
+            if find(a,&kv.Key) != Some(i){
+                continue;
+            }
+            let child = format!("{}/{}",path,escapePointerToken(&kv.Key));
+            match find(b,&kv.Key) {
+                Some(j) => self.diff(&child,&kv.Value,&b[j].Value),
+                None => self.push(&child,ChangeKind::Removed,Some(&kv.Value),None),
+            }
+        }
+        for (i,kv) in b.iter().enumerate(){
+            if find(b,&kv.Key) == Some(i) && find(a,&kv.Key).is_none(){
+                self.push(&format!("{}/{}",path,escapePointerToken(&kv.Key)),ChangeKind::Added,None,Some(&kv.Value));
+            }
+        }
+    }
+
+    fn arrayKey(&self,path: &str) -> Option<&str>{
+        match self.opts.array_keys.iter().find(|(p,_)| p == path) {
+            Some((_,key)) => Some(key),
+            None => match &self.opts.arrays {
+                ArrayMode::ByKey(key) => Some(key),
+                ArrayMode::Ordered => None,
+            },
+        }
+    }
+
+    fn diffArray(&mut self,path: &str,a: &[DxValue],b: &[DxValue]){
+        if let Some(key) = self.arrayKey(path){
+            //数字键按精确值比较，1和1.0是同一个键；其它键用规范化文本比较
+            let keyOf = |v: &DxValue| v.get_byName(key).and_then(|k| match exactNumber(k) {
+                Some((negative,digits,exp)) => Some(format!("{}{}e{}",if negative { "-" } else { "" },digits,exp)),
+                None => k.to_canonical().ok(),
+            });
+            let ka: Option<Vec<String>> = a.iter().map(keyOf).collect();
+            let kb: Option<Vec<String>> = b.iter().map(keyOf).collect();
+            if let (Some(ka),Some(kb)) = (ka,kb){
+                let mut index: HashMap<&str,usize> = HashMap::new();
+                for (j,k) in kb.iter().enumerate(){
+                    index.entry(k.as_str()).or_insert(j);
+                }
+                let old: HashMap<&str,usize> = ka.iter().enumerate().rev().map(|(i,k)| (k.as_str(),i)).collect();
+                //修改和删除都用旧数组的下标，同一个路径只对应一个元素；新增的元素没有旧下标，用 -
+                for (i,k) in ka.iter().enumerate(){
+                    match index.get(k.as_str()) {
+                        Some(&j) if old[k.as_str()] == i => self.diff(&format!("{}/{}",path,i),&a[i],&b[j]),
+                        _ => self.push(&format!("{}/{}",path,i),ChangeKind::Removed,Some(&a[i]),None),
+                    }
+                }
+                for (j,k) in kb.iter().enumerate(){
+                    if !old.contains_key(k.as_str()) || index[k.as_str()] != j{
+                        self.push(&format!("{}/-",path),ChangeKind::Added,None,Some(&b[j]));
+                    }
+                }
+                return;
+            }
+        }
+        for i in 0..a.len().max(b.len()){
+            let child = format!("{}/{}",path,i);
+            match (a.get(i),b.get(i)) {
+                (Some(x),Some(y)) => self.diff(&child,x,y),
+                (Some(x),None) => self.push(&child,ChangeKind::Removed,Some(x),None),
+                (None,Some(y)) => self.push(&child,ChangeKind::Added,None,Some(y)),
+                (None,None) => {},
+            }
+        }
+    }
+}
+
+pub fn diff(a: &DxValue,b: &DxValue,opts: &DiffOptions) -> Vec<Change>{
+    let mut differ = Differ{opts,changes: Vec::new()};
+    differ.diff("",a,b);
+    differ.changes
+}
+
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const RESET: &str = "\x1b[0m";
+
+impl Display for Change{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&render(std::slice::from_ref(self),false))
+    }
+}
+
+/// NaN/Infinity不能输出为JSON，按Display输出
+fn valueText(v: &DxValue) -> String{
+    json::toJson(v).unwrap_or_else(|_| v.to_string())
+}
+
+/// 统一diff风格的文本：每个变更一个 @@ path @@ 头，旧值以-开头，新值以+开头；
+/// 值按json::toJson输出，不丢精度；color为true时带ANSI颜色
+pub fn render(changes: &[Change],color: bool) -> String{
+    let paint = |out: &mut String,c: &str,line: String| {
+        if color{
+            let _ = writeln!(out,"{}{}{}",c,line,RESET);
+        }else{
+            let _ = writeln!(out,"{}",line);
+        }
+    };
+    let mut out = String::new();
+    for ch in changes{
+        let path = if ch.path.is_empty() { "/" } else { &ch.path };
+        let note = match ch.kind {
+            ChangeKind::TypeChanged => format!(" {} -> {}",ch.old.as_ref().map_or("",kindOf),ch.new.as_ref().map_or("",kindOf)),
+            _ => String::new(),
+        };
+        paint(&mut out,YELLOW,format!("@@ {} @@{}",path,note));
+        if let Some(old) = &ch.old{
+            paint(&mut out,RED,format!("-{}",valueText(old)));
+        }
+        if let Some(new) = &ch.new{
+            paint(&mut out,GREEN,format!("+{}",valueText(new)));
+        }
+    }
+    out
+}
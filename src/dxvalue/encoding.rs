@@ -0,0 +1,121 @@
+//! 输入编码的识别和转换：UTF-16/UTF-32(根据BOM或开头的0字节判断)、GBK/GB18030，
+//! 解析前统一转为UTF-8，出错位置换算回原始输入中的字节位置
+
+use std::borrow::Cow;
+use encoding_rs::{DecoderResult, Encoding, GB18030, GBK, UTF_16BE, UTF_16LE};
+use crate::dxvalue::json::{errJsonReson, errorJson};
+use crate::dxvalue::scan;
+
+/// 输入编码，Auto根据BOM识别UTF-8/16/32，没有BOM时按RFC 4627的0字节规律判断；
+/// 识别为UTF-8时不是合法UTF-8的输入直接报错，GBK/GB18030需要显式指定
+#[derive(Debug,Clone,Copy,PartialEq,Default)]
+pub enum InputEncoding{
+    #[default]
+    Auto,
+    Utf8,
+    Utf16LE,
+    Utf16BE,
+    Utf32LE,
+    Utf32BE,
+    Gbk,
+    Gb18030,
+}
+
+/// 识别编码，返回编码和BOM的长度
+pub fn detect(buf: &[u8]) -> (InputEncoding,usize){
+    match buf {
+        [0xEF,0xBB,0xBF,..] => (InputEncoding::Utf8,3),
+        [0xFF,0xFE,0,0,..] => (InputEncoding::Utf32LE,4),
+        [0,0,0xFE,0xFF,..] => (InputEncoding::Utf32BE,4),
+        [0xFF,0xFE,..] => (InputEncoding::Utf16LE,2),
+        [0xFE,0xFF,..] => (InputEncoding::Utf16BE,2),
+        //JSON文本的前两个字符都是ASCII
+        [0,0,0,a,..] if *a != 0 => (InputEncoding::Utf32BE,0),
+        [a,0,0,0,..] if *a != 0 => (InputEncoding::Utf32LE,0),
+        [0,a,..] if *a != 0 => (InputEncoding::Utf16BE,0),
+        [a,0,..] if *a != 0 => (InputEncoding::Utf16LE,0),
+        _ => (InputEncoding::Utf8,0),
+    }
+}
+
+/// 去掉和encoding一致的BOM
+fn bomLen(buf: &[u8],encoding: InputEncoding) -> usize{
+    match detect(buf) {
+        (enc,n) if enc == encoding => n,
+        _ => 0,
+    }
+}
+
+/// 把输入转为UTF-8，UTF-8输入只校验不复制；返回实际使用的编码
+pub fn decode(buf: &[u8],encoding: InputEncoding) -> Result<(Cow<'_,[u8]>,InputEncoding),errorJson>{
+    let encoding = match encoding {
+        InputEncoding::Auto => detect(buf).0,
+        enc => enc,
+    };
+    let start = bomLen(buf,encoding);
+    let src = &buf[start..];
+    let out = match encoding {
+        InputEncoding::Auto | InputEncoding::Utf8 => {
+            return match scan::validateUtf8(buf) {
+                Ok(_) => Ok((Cow::Borrowed(buf),InputEncoding::Utf8)),
+                Err(n) => Err(errorJson::new(n as isize,errJsonReson::JET_Encoding)),
+            };
+        },
+        InputEncoding::Utf16LE => decodeWith(UTF_16LE,src,start)?,
+        InputEncoding::Utf16BE => decodeWith(UTF_16BE,src,start)?,
+        InputEncoding::Gbk => decodeWith(GBK,src,start)?,
+        InputEncoding::Gb18030 => decodeWith(GB18030,src,start)?,
+        InputEncoding::Utf32LE | InputEncoding::Utf32BE => {
+            let mut out = String::with_capacity(src.len() / 4);
+            for (i,c) in src.chunks(4).enumerate(){
+                let pos = (start + i * 4) as isize;
+                if c.len() < 4{
+                    return Err(errorJson::new(pos,errJsonReson::JET_Encoding));
+                }
+                let b = [c[0],c[1],c[2],c[3]];
+                let code = if encoding == InputEncoding::Utf32LE { u32::from_le_bytes(b) } else { u32::from_be_bytes(b) };
+                match char::from_u32(code) {
+                    Some(ch) => out.push(ch),
+                    None => return Err(errorJson::new(pos,errJsonReson::JET_Encoding)),
+                }
+            }
+            out.into_bytes()
+        },
+    };
+    Ok((Cow::Owned(out),encoding))
+}
+
+fn decodeWith(enc: &'static Encoding,src: &[u8],base: usize) -> Result<Vec<u8>,errorJson>{
+    let mut decoder = enc.new_decoder_without_bom_handling();
+    let cap = decoder.max_utf8_buffer_length_without_replacement(src.len()).unwrap_or(src.len() * 3 + 16);
+    let mut out = vec![0u8;cap];
+    let (result,read,written) = decoder.decode_to_utf8_without_replacement(src,&mut out,true);
+    match result {
+        DecoderResult::InputEmpty => {
+            out.truncate(written);
+            Ok(out)
+        },
+        //read包含了出错的字节和其后已读取的extra个字节
+        DecoderResult::Malformed(bad,extra) => {
+            Err(errorJson::new((base + read - bad as usize - extra as usize) as isize,errJsonReson::JET_Encoding))
+        },
+        DecoderResult::OutputFull => Err(errorJson::new((base + read) as isize,errJsonReson::JET_Encoding)),
+    }
+}
+
+/// 把转码后UTF-8文本中的位置换算为原始输入中的字节位置
+pub(crate) fn originalPos(buf: &[u8],utf8: &[u8],pos: usize,encoding: InputEncoding) -> usize{
+    let mut pos = pos.min(utf8.len());
+    while pos > 0 && pos < utf8.len() && utf8[pos] & 0xC0 == 0x80{
+        pos -= 1;
+    }
+    let prefix = String::from_utf8_lossy(&utf8[..pos]);
+    let start = bomLen(buf,encoding);
+    start + match encoding {
+        InputEncoding::Utf16LE | InputEncoding::Utf16BE => prefix.chars().map(|c| c.len_utf16() * 2).sum(),
+        InputEncoding::Utf32LE | InputEncoding::Utf32BE => prefix.chars().count() * 4,
+        InputEncoding::Gbk => GBK.encode(&prefix).0.len(),
+        InputEncoding::Gb18030 => GB18030.encode(&prefix).0.len(),
+        InputEncoding::Auto | InputEncoding::Utf8 => return pos,
+    }
+}
@@ -0,0 +1,262 @@
+//! 从样本文档推断JSON Schema或简洁的类型描述。同一位置出现的不同类型合并为联合类型，
+//! 整数取能容纳所有样本的最小类型，null记为可空，不是每个样本都有的键记为可选
+
+use std::sync::Arc;
+use crate::dxvalue::{numberFromText, DxDateTime, DxValue, Key_Value};
+
+/// 某个位置上见过的所有取值的汇总
+#[derive(Debug,Clone,Default)]
+struct Shape{
+    null: bool,
+    boolean: bool,
+    /// 见过的整数范围(最小值,最大值)
+    int: Option<(i128,u128)>,
+    /// 见过非整数的数字
+    float: bool,
+    string: usize,
+    datetime: usize,    //能按RFC 3339解析的字符串个数
+    binary: usize,
+    object: Option<ObjectShape>,
+    /// 所有数组元素合并后的类型，还没见过元素时为None
+    array: Option<Option<Box<Shape>>>,
+}
+
+#[derive(Debug,Clone,Default)]
+struct ObjectShape{
+    count: usize,                   //见过的对象个数
+    fields: Vec<(String,usize,Shape)>, //按第一次出现的顺序，usize是出现次数
+}
+
+/// 数字按整数范围归类
+fn intRange(v: &DxValue) -> Option<(i128,u128)>{
+    match v.try_as_integer::<i128>() {
+        Ok(n) => Some((n,if n < 0 { 0 } else { n as u128 })),
+        Err(_) => v.try_as_integer::<u128>().ok().map(|n| (0,n)),
+    }
+}
+
+impl Shape{
+    fn add(&mut self,v: &DxValue){
+        match v {
+            DxValue::None => self.null = true,
+            DxValue::Boolean(_) => self.boolean = true,
+            DxValue::Int(_) | DxValue::Int32(_) | DxValue::Int64(_) | DxValue::UInt64(_) | DxValue::Int128(_) | DxValue::UInt128(_) => {
+                self.addInt(intRange(v));
+            },
+            DxValue::Float(_) | DxValue::Double(_) | DxValue::Decimal(_) => self.float = true,
+            DxValue::RawNumber(text) => match numberFromText(text) {
+                Some(DxValue::Double(_)) | None => self.float = true,
+                Some(n) => self.addInt(intRange(&n)),
+            },
+            DxValue::String(s) => {
+                self.string += 1;
+                if DxDateTime::parse_rfc3339(s).is_some(){
+                    self.datetime += 1;
+                }
+            },
+            DxValue::DateTime(_) => {
+                self.string += 1;
+                self.datetime += 1;
+            },
+            DxValue::Binary(_) => {
+                self.string += 1;
+                self.binary += 1;
+            },
+            DxValue::Object(members) => {
+                let obj = self.object.get_or_insert_with(ObjectShape::default);
+                obj.count += 1;
+                for kv in members.iter(){
+                    match obj.fields.iter_mut().find(|f| f.0 == kv.Key) {
+                        Some(f) => {
+                            f.1 += 1;
+                            f.2.add(&kv.Value);
+                        },
+                        None => {
+                            let mut shape = Shape::default();
+                            shape.add(&kv.Value);
+                            obj.fields.push((kv.Key.clone(),1,shape));
+                        },
+                    }
+                }
+            },
+            DxValue::Array(items) => {
+                let elem = self.array.get_or_insert(None);
+                for item in items.iter(){
+                    elem.get_or_insert_with(Box::default).add(item);
+                }
+            },
+            DxValue::Raw(raw) => {
+                if let Some(v) = raw.value(){
+                    self.add(v);
+                }
+            },
+        }
+    }
+
+    fn addInt(&mut self,range: Option<(i128,u128)>){
+        match (range,&mut self.int) {
+            (None,_) => self.float = true,
+            (Some(r),None) => self.int = Some(r),
+            (Some((lo,hi)),Some(cur)) => *cur = (cur.0.min(lo),cur.1.max(hi)),
+        }
+    }
+
+    /// 能容纳见过的所有整数的最小类型
+    fn intKind(&self) -> Option<&'static str>{
+        let (lo,hi) = self.int?;
+        Some(if lo >= i32::MIN as i128 && hi <= i32::MAX as u128{
+            "int32"
+        }else if lo >= i64::MIN as i128 && hi <= i64::MAX as u128{
+            "int64"
+        }else if lo >= 0 && hi <= u64::MAX as u128{
+            "uint64"
+        }else if hi <= i128::MAX as u128{
+            "int128"
+        }else{
+            "uint128"
+        })
+    }
+
+    /// 字符串都是同一种格式时的format
+    fn stringFormat(&self) -> Option<&'static str>{
+        if self.string == 0{
+            None
+        }else if self.binary == self.string{
+            Some("binary")
+        }else if self.datetime == self.string{
+            Some("date-time")
+        }else{
+            None
+        }
+    }
+
+    fn toSchema(&self) -> DxValue{
+        let mut types = Vec::new();
+        if self.object.is_some(){
+            types.push("object");
+        }
+        if self.array.is_some(){
+            types.push("array");
+        }
+        if self.string > 0{
+            types.push("string");
+        }
+        //同时见过整数和小数时只写number
+        if self.float{
+            types.push("number");
+        }else if self.int.is_some(){
+            types.push("integer");
+        }
+        if self.boolean{
+            types.push("boolean");
+        }
+        if self.null{
+            types.push("null");
+        }
+        let mut schema = DxValue::newObject();
+        match types.as_slice() {
+            [] => return DxValue::Boolean(true),
+            [t] => schema.setKeyString("type",t),
+            ts => schema.setKeyValue("type",DxValue::Array(Arc::new(ts.iter().map(|t| DxValue::String(t.to_string())).collect()))),
+        }
+        //整数、小数都有时format没有意义
+        let numberFormat = if self.float { None } else { self.intKind() };
+        match (self.stringFormat(),numberFormat) {
+            (Some("binary"),None) => schema.setKeyString("contentEncoding","base64"),
+            (Some(f),None) | (None,Some(f)) => schema.setKeyString("format",f),
+            _ => {},
+        }
+        if let Some(obj) = &self.object{
+            let mut props = DxValue::newObject();
+            let mut required = Vec::new();
+            for (name,count,shape) in &obj.fields{
+                props.setKeyValue(name,shape.toSchema());
+                if *count == obj.count{
+                    required.push(DxValue::String(name.clone()));
+                }
+            }
+            schema.setKeyValue("properties",props);
+            if !required.is_empty(){
+                schema.setKeyValue("required",DxValue::Array(Arc::new(required)));
+            }
+        }
+        if let Some(Some(elem)) = &self.array{
+            schema.setKeyValue("items",elem.toSchema());
+        }
+        schema
+    }
+
+    fn describe(&self) -> String{
+        let mut parts = Vec::new();
+        if let Some(obj) = &self.object{
+            let fields: Vec<String> = obj.fields.iter().map(|(name,count,shape)| {
+                format!("{}{}: {}",name,if *count < obj.count { "?" } else { "" },shape.describe())
+            }).collect();
+            parts.push(format!("{{{}}}",fields.join(", ")));
+        }
+        if let Some(elem) = &self.array{
+            parts.push(format!("[{}]",elem.as_ref().map_or("any".to_string(),|e| e.describe())));
+        }
+        match self.stringFormat() {
+            Some("binary") => parts.push("binary".to_string()),
+            Some(_) => parts.push("datetime".to_string()),
+            None if self.string > 0 => parts.push("string".to_string()),
+            None => {},
+        }
+        if self.float{
+            parts.push("double".to_string());
+        }else if let Some(kind) = self.intKind(){
+            parts.push(kind.to_string());
+        }
+        if self.boolean{
+            parts.push("bool".to_string());
+        }
+        if self.null{
+            parts.push("null".to_string());
+        }
+        if parts.is_empty() { "any".to_string() } else { parts.join(" | ") }
+    }
+}
+
+/// 逐个加入样本，随时可以输出推断结果
+#[derive(Debug,Clone,Default)]
+pub struct SchemaInferrer{
+    shape: Shape,
+    samples: usize,
+}
+
+impl SchemaInferrer{
+    pub fn new() -> Self{
+        SchemaInferrer::default()
+    }
+
+    pub fn add(&mut self,sample: &DxValue){
+        self.shape.add(sample);
+        self.samples += 1;
+    }
+
+    pub fn samples(&self) -> usize{
+        self.samples
+    }
+
+    /// 推断的JSON Schema(draft 2020-12)，整数带上int32/int64等format，
+    /// 所有样本中都有的键列入required
+    pub fn to_schema(&self) -> DxValue{
+        let mut schema = self.shape.toSchema();
+        if let DxValue::Object(obj) = &mut schema{
+            Arc::make_mut(obj).insert(0,Key_Value::new_value("$schema",DxValue::String("https://json-schema.org/draft/2020-12/schema".to_string())));
+        }
+        schema
+    }
+
+    /// 简洁的类型描述，如 {id: int64, name: string, tags?: [string], score: double | null}
+    pub fn describe(&self) -> String{
+        self.shape.describe()
+    }
+}
+
+pub fn inferSchema(samples: &[DxValue]) -> DxValue{
+    let mut inferrer = SchemaInferrer::new();
+    samples.iter().for_each(|s| inferrer.add(s));
+    inferrer.to_schema()
+}
@@ -1,8 +1,14 @@
 use std::{io,fs,fmt,result};
+use std::fmt::Write;
 use std::io::Read;
-use std::fs::read;
-use crate::dxvalue::DxValue;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::Arc;
+use crate::dxvalue::encoding::{self, InputEncoding};
+use crate::dxvalue::sourcemap::SourceMap;
+use crate::dxvalue::{canonical, scan, errValueReson, writeJsonString, DxValue, DxValueRef, DxRawJson, DxDateTime, DxDecimal, Key_Value, base64, numberFromText, escapePointerToken};
 
+#[derive(Clone,Copy,PartialEq)]
 pub enum errJsonReson{
     JET_NoObjBack,           //缺少}
     JET_NoArrBack,           //缺少]
@@ -12,7 +18,23 @@ pub enum errJsonReson{
     JET_NoKVSplit,
     JET_NoValueSplit,
     JET_Invalidate,
-    JET_UnParse
+    JET_UnParse,
+    JET_NumberRange,         //数字超出可精确表示的范围
+    JET_Comment,             //严格模式下出现注释
+    JET_TrailingComma,       //严格模式下最后一个元素后面有逗号
+    JET_UnquotedKey,         //键没有引号
+    JET_SingleQuote,         //单引号字符串
+    JET_HexNumber,           //十六进制数字
+    JET_NonFinite,           //Infinity/NaN
+    JET_NumberFormat,        //前导0、前导/末尾小数点、正号等非法数字写法
+    JET_BadEscape,           //非法的转义字符
+    JET_ControlChar,         //字符串中未转义的控制字符
+    JET_Encoding,            //输入不符合指定(或识别出)的编码
+    JET_TooDeep,             //嵌套层数超过max_depth
+    JET_StringTooLong,       //字符串超过max_string_len
+    JET_ContainerTooLarge,   //对象或数组的元素个数超过max_container_len
+    JET_InputTooLarge,       //输入超过max_total_bytes
+    JET_DuplicateKey,        //duplicate_keys为Error时对象中出现重复的键
 }
 
 impl fmt::Debug for errJsonReson{
@@ -24,9 +46,25 @@ impl fmt::Debug for errJsonReson{
             errJsonReson::JET_NoKeyStart=>f.write_str("JET_NoKeyStart"),
             errJsonReson::JET_NoKVSplit=>f.write_str("JET_NoKVSplit"),
             errJsonReson::JET_NoObjBack=>f.write_str("JET_NoObjBack"),
-            errJsonReson::JET_NoStrEnd=>f.write_str("JET_NoObjBack"),
-            errJsonReson::JET_NoStrStart=>f.write_str("JET_NoObjBack"),
+            errJsonReson::JET_NoStrEnd=>f.write_str("JET_NoStrEnd"),
+            errJsonReson::JET_NoStrStart=>f.write_str("JET_NoStrStart"),
             errJsonReson::JET_NoValueSplit=>f.write_str("JET_NoValueSplit"),
+            errJsonReson::JET_NumberRange=>f.write_str("JET_NumberRange"),
+            errJsonReson::JET_Comment=>f.write_str("JET_Comment"),
+            errJsonReson::JET_TrailingComma=>f.write_str("JET_TrailingComma"),
+            errJsonReson::JET_UnquotedKey=>f.write_str("JET_UnquotedKey"),
+            errJsonReson::JET_SingleQuote=>f.write_str("JET_SingleQuote"),
+            errJsonReson::JET_HexNumber=>f.write_str("JET_HexNumber"),
+            errJsonReson::JET_NonFinite=>f.write_str("JET_NonFinite"),
+            errJsonReson::JET_NumberFormat=>f.write_str("JET_NumberFormat"),
+            errJsonReson::JET_BadEscape=>f.write_str("JET_BadEscape"),
+            errJsonReson::JET_ControlChar=>f.write_str("JET_ControlChar"),
+            errJsonReson::JET_Encoding=>f.write_str("JET_Encoding"),
+            errJsonReson::JET_TooDeep=>f.write_str("JET_TooDeep"),
+            errJsonReson::JET_StringTooLong=>f.write_str("JET_StringTooLong"),
+            errJsonReson::JET_ContainerTooLarge=>f.write_str("JET_ContainerTooLarge"),
+            errJsonReson::JET_InputTooLarge=>f.write_str("JET_InputTooLarge"),
+            errJsonReson::JET_DuplicateKey=>f.write_str("JET_DuplicateKey"),
         }
     }
 }
@@ -34,7 +72,44 @@ impl fmt::Debug for errJsonReson{
 
 pub struct errorJson{
     errPos: isize,
-    reson: errJsonReson
+    reson: errJsonReson,
+    line: usize,        //按行读取(NDJSON)时出错的行号，从1开始，0表示不适用
+}
+
+impl errorJson {
+    pub(crate) fn new(errPos: isize,reson: errJsonReson) -> Self{
+        errorJson{errPos,reson,line: 0}
+    }
+
+    pub(crate) fn with_line(mut self,line: usize) -> Self{
+        self.line = line;
+        self
+    }
+
+    pub(crate) fn with_pos(mut self,pos: usize) -> Self{
+        self.errPos = pos as isize;
+        self
+    }
+
+    /// 位置加上片段在整个输入中的偏移
+    pub(crate) fn offset(mut self,base: usize) -> Self{
+        self.errPos += base as isize;
+        self
+    }
+
+    /// 出错的字节位置
+    pub fn pos(&self) -> isize{
+        self.errPos
+    }
+
+    pub fn reson(&self) -> errJsonReson{
+        self.reson
+    }
+
+    /// 出错的行号(从1开始)，按行解析(NDJSON)时才有，否则为0；pos总是整个输入中的字节位置
+    pub fn line(&self) -> usize{
+        self.line
+    }
 }
 
 impl fmt::Debug for errorJson {
@@ -42,50 +117,1178 @@ impl fmt::Debug for errorJson {
         f.debug_struct("")
             .field("errPos",&self.errPos)
             .field("reson",&self.reson)
+            .field("line",&self.line)
             .finish()
     }
 }
 
 pub type Result = std::result::Result<DxValue, errorJson>;
 
+/// JSON解析选项
+#[derive(Debug,Clone)]
+pub struct ParseOptions{
+    /// 这些键下的字符串值如果是合法的base64，解析为DxValue::Binary
+    pub base64_keys: Vec<String>,
+    /// 符合RFC 3339格式的字符串解析为DxValue::DateTime
+    pub parse_datetime: bool,
+    /// 带小数或指数的数字解析为DxValue::Decimal而不是Double，
+    /// 有效数字超过38位时返回JET_NumberRange
+    pub decimal: bool,
+    /// 数字保留原始文本(DxValue::RawNumber)，写回时逐字节不变，优先于decimal
+    pub keep_number_text: bool,
+    /// 允许 // 行注释和 /* */ 块注释(JSONC)
+    pub allow_comments: bool,
+    /// 允许对象和数组的最后一个元素后面带逗号
+    pub allow_trailing_commas: bool,
+    /// 完整的JSON5语法：在注释和末尾逗号之外，还允许无引号的键、单引号字符串、
+    /// 十六进制数字、Infinity/NaN、前导/末尾小数点、正号以及字符串续行
+    pub json5: bool,
+    /// 大于0时，深度达到lazy_depth的对象和数组(根节点深度为0)只校验不构造，
+    /// 保存为DxValue::Raw，第一次访问时才展开。只在严格JSON模式下生效
+    pub lazy_depth: usize,
+    /// 输入编码，默认自动识别；非UTF-8的输入先转为UTF-8再解析，出错位置仍是原始输入中的字节位置
+    pub encoding: InputEncoding,
+    /// 以下为处理不可信输入时的限制，0表示不限制。
+    /// 对象和数组的最大嵌套层数，默认128；设为0时过深的输入可能导致栈溢出
+    pub max_depth: usize,
+    /// 字符串(含键)解码后的最大字节数
+    pub max_string_len: usize,
+    /// 单个对象或数组的最大元素个数
+    pub max_container_len: usize,
+    /// 一次解析的最大输入字节数(NDJSON为每一行)
+    pub max_total_bytes: usize,
+    /// 对象中出现重复的键时的处理方式
+    pub duplicate_keys: DuplicateKeys,
+}
+
+/// 重复键的处理方式
+#[derive(Debug,Clone,Copy,PartialEq,Default)]
+pub enum DuplicateKeys{
+    /// 保留第一次出现的值
+    First,
+    /// 保留最后一次出现的值，位置仍是第一次出现的位置(与JavaScript的JSON.parse一致)
+    Last,
+    /// 返回JET_DuplicateKey
+    Error,
+    /// 全部保留，xx_byName取第一个，values_byName取全部
+    #[default]
+    KeepAll,
+}
+
+/// 对象成员的键查找，成员较多时改用HashMap，避免重复键检查退化为O(n²)
+#[derive(Default)]
+struct keyIndex{
+    map: Option<HashMap<String,usize>>,
+}
+
+impl keyIndex{
+    /// 返回key已有成员的下标；不存在时记下它将是第len个成员
+    fn lookup<'k>(&mut self,key: &str,len: usize,keyAt: impl Fn(usize) -> &'k str) -> Option<usize>{
+        if self.map.is_none() && len >= 32{
+            self.map = Some((0..len).map(|i| (keyAt(i).to_string(),i)).collect());
+        }
+        match &mut self.map {
+            Some(map) => {
+                if let Some(&i) = map.get(key){
+                    return Some(i);
+                }
+                map.insert(key.to_string(),len);
+                None
+            },
+            None => (0..len).find(|&i| keyAt(i) == key),
+        }
+    }
+}
+
+impl Default for ParseOptions{
+    fn default() -> Self{
+        ParseOptions{
+            base64_keys: Vec::new(),
+            parse_datetime: false,
+            decimal: false,
+            keep_number_text: false,
+            allow_comments: false,
+            allow_trailing_commas: false,
+            json5: false,
+            lazy_depth: 0,
+            encoding: InputEncoding::Auto,
+            max_depth: 128,
+            max_string_len: 0,
+            max_container_len: 0,
+            max_total_bytes: 0,
+            duplicate_keys: DuplicateKeys::KeepAll,
+        }
+    }
+}
+
+impl ParseOptions{
+    /// JSONC：允许注释和末尾逗号
+    pub fn jsonc() -> Self{
+        ParseOptions{allow_comments: true,allow_trailing_commas: true,..Default::default()}
+    }
+
+    pub fn json5() -> Self{
+        ParseOptions{allow_comments: true,allow_trailing_commas: true,json5: true,..Default::default()}
+    }
+}
+
+//JSON5数字中标准JSON表示不了的值直接返回
+enum json5Number{
+    Text(String,bool),
+    Value(DxValue),
+}
+
+fn isIdentStart(c: u8) -> bool{
+    c.is_ascii_alphabetic() || c == b'$' || c == b'_' || c >= 0x80
+}
+
+fn isIdentPart(c: u8) -> bool{
+    isIdentStart(c) || c.is_ascii_digit()
+}
+
+/// 解析时记录的节点位置，path为JSON Pointer，
+/// key_start是对象成员的键(含引号)起始位置，[start,end)是值本身的字节范围
+#[derive(Debug,Clone)]
+pub(crate) struct NodeSpan{
+    pub path: String,
+    pub key_start: Option<usize>,
+    pub start: usize,
+    pub end: usize,
+}
+
 pub fn loadFromFile(file: &str)->Result{
+    loadFromFileWithOptions(file,&ParseOptions::default())
+}
+
+pub fn loadFromFileWithOptions(file: &str,opts: &ParseOptions)->Result{
     let f = fs::File::open(file);
     match f {
         io::Result::Ok(mut fhandle)=>{
+            //超过大小限制时不读取文件内容
+            if let Ok(meta) = fhandle.metadata(){
+                checkInputSize(meta.len() as usize,opts)?;
+            }
             let mut buf: Vec<u8> = Vec::new();
-            if let Ok(_) = fhandle.read_to_end(&mut buf){
-                return parseJson(buf)
+            if fhandle.read_to_end(&mut buf).is_ok(){
+                return parseJsonWithOptions(&buf,opts)
             }
         },
-        io::Result::Err(e)=>{
-            return result::Result::Err(errorJson{errPos:0,reson: errJsonReson::JET_UnParse});
+        io::Result::Err(_)=>{
+            return result::Result::Err(errorJson::new(0,errJsonReson::JET_UnParse));
         }
     }
-    result::Result::Err(errorJson{errPos:0,reson: errJsonReson::JET_UnParse})
+    result::Result::Err(errorJson::new(0,errJsonReson::JET_UnParse))
+}
+
+/// 通过内存映射读取文件并直接解析，不把整个文件复制到堆上
+#[cfg(feature = "mmap")]
+pub fn load_mmap(file: &str)->Result{
+    load_mmap_with_options(file,&ParseOptions::default())
+}
+
+#[cfg(feature = "mmap")]
+pub fn load_mmap_with_options(file: &str,opts: &ParseOptions)->Result{
+    let f = match fs::File::open(file) {
+        Ok(f) => f,
+        Err(_) => return Err(errorJson::new(0,errJsonReson::JET_UnParse)),
+    };
+    //映射期间文件被截断或改写时行为未定义，只用于只读的数据文件
+    let map = match unsafe { memmap2::Mmap::map(&f) } {
+        Ok(map) => map,
+        Err(_) => return Err(errorJson::new(0,errJsonReson::JET_UnParse)),
+    };
+    parseJsonWithOptions(&map,opts)
+}
+
+/// 跳过空白，返回剩余部分和跳过的字节数
+fn skipWB(b: &[u8])->(&[u8],usize){
+    let n = scan::skipWhitespace(b);
+    (&b[n..],n)
+}
+
+pub fn parseJson(buf: Vec<u8>)->Result{
+    parseJsonWithOptions(&buf,&ParseOptions::default())
+}
+
+pub fn parseJsonWithOptions(buf: &[u8],opts: &ParseOptions)->Result{
+    runParser(buf,opts,false).map(|(value,_)| value)
+}
+
+fn checkInputSize(len: usize,opts: &ParseOptions) -> result::Result<(),errorJson>{
+    if opts.max_total_bytes > 0 && len > opts.max_total_bytes{
+        return Err(errorJson::new(opts.max_total_bytes as isize,errJsonReson::JET_InputTooLarge));
+    }
+    Ok(())
 }
 
-fn skipWB<'a>(b: &'a[u8])->(&'a [u8],usize){
-    if b.len() == 0 || b[0] > 0x20{
-        return (b,0);
+/// 只校验语法，不构造值
+pub fn validateJson(buf: &[u8],opts: &ParseOptions) -> result::Result<(),errorJson>{
+    checkInputSize(buf.len(),opts)?;
+    let mut parser = newParser(buf,opts,false);
+    parser.skipWhite();
+    if opts.allow_comments || opts.allow_trailing_commas || opts.json5{
+        parser.parseValueRef()?;
+    }else{
+        parser.skipValue()?;
     }
-    if b.len() == 0 || b[0] != 0x20 && b[0] != 0x0A && b[0] != 0x09 && b[0] != 0x0D {
-        return (b,0);
+    parser.skipWhite();
+    if parser.pos < buf.len(){
+        return Err(parser.unexpected(errJsonReson::JET_Invalidate));
     }
-    for i in 1..b.len()-1{
-        if b[i] != 0x20 && b[i] != 0x0A && b[i] != 0x09 && b[i] != 0x0D {
-            return (&b[i..],i-1);
-        }
+    Ok(())
+}
+
+/// 解析成借用buf的DxValueRef，没有转义的字符串和键不复制。
+/// 只使用语法相关的选项(注释、末尾逗号、JSON5、重复键)，数字等的类型转换在to_value_with时进行
+pub fn parseJsonRef<'a>(buf: &'a [u8],opts: &ParseOptions) -> result::Result<DxValueRef<'a>,errorJson>{
+    checkInputSize(buf.len(),opts)?;
+    let mut parser = newParser(buf,opts,false);
+    parser.skipWhite();
+    let value = parser.parseValueRef()?;
+    parser.skipWhite();
+    if parser.pos < buf.len(){
+        return Err(parser.unexpected(errJsonReson::JET_Invalidate));
     }
-    (b,0)
+    Ok(value)
 }
 
-pub fn parseJson(buf: Vec<u8>)->Result{
+/// 解析UTF-8文本并记录每个节点的字节位置
+pub(crate) fn parseJsonSpans(text: &str,opts: &ParseOptions) -> result::Result<(DxValue,Vec<NodeSpan>),errorJson>{
+    checkInputSize(text.len(),opts)?;
+    runParserUtf8(text.as_bytes(),opts,true,true).map(|(value,spans)| (value,spans.unwrap_or_default()))
+}
+
+/// 解析并返回每个节点的位置表(字节/行/列)，lazy_depth以下的延迟节点只记录自身。
+/// 非UTF-8输入的字节位置是转码为UTF-8之后的位置，行列不受影响
+pub fn parseJsonWithSpans(buf: &[u8],opts: &ParseOptions) -> result::Result<(DxValue,SourceMap),errorJson>{
+    runParser(buf,opts,true).map(|(value,map)| (value,map.unwrap_or_default()))
+}
+
+/// 同parseJsonWithSpans，位置表中带上文件名
+pub fn loadFromFileWithSpans(file: &str,opts: &ParseOptions) -> result::Result<(DxValue,SourceMap),errorJson>{
+    let buf = fs::read(file).map_err(|_| errorJson::new(0,errJsonReson::JET_UnParse))?;
+    parseJsonWithSpans(&buf,opts).map(|(value,map)| (value,map.with_file(file)))
+}
+
+/// 输出紧凑的JSON文本，保持键的顺序；与Display不同，浮点数按最短往返表示输出不丢精度，
+/// 整数、Decimal和RawNumber原样输出。NaN/Infinity不是合法JSON，返回VET_NonFinite
+pub fn toJson(value: &DxValue) -> result::Result<String,errValueReson>{
+    let mut out = String::new();
+    writeJson(&mut out,value)?;
+    Ok(out)
+}
+
+fn writeJson(out: &mut String,value: &DxValue) -> result::Result<(),errValueReson>{
+    match value {
+        //f32按自身的最短表示，0.1f32输出0.1
+        DxValue::Float(v) => out.push_str(&canonical::formatNumber(v.to_string().parse().unwrap_or(f64::NAN))?),
+        DxValue::Double(v) => out.push_str(&canonical::formatNumber(*v)?),
+        //延迟节点的原文可能带注释或JSON5写法，展开后重新输出
+        DxValue::Raw(raw) => writeJson(out,&raw.parse().map_err(|_| errValueReson::VET_TypeMismatch)?)?,
+        DxValue::Array(arr) => {
+            out.push('[');
+            for (i,v) in arr.iter().enumerate(){
+                if i > 0{
+                    out.push(',');
+                }
+                writeJson(out,v)?;
+            }
+            out.push(']');
+        },
+        DxValue::Object(obj) => {
+            out.push('{');
+            for (i,kv) in obj.iter().enumerate(){
+                if i > 0{
+                    out.push(',');
+                }
+                let _ = writeJsonString(out,&kv.Key);
+                out.push(':');
+                writeJson(out,&kv.Value)?;
+            }
+            out.push('}');
+        },
+        //其余类型的Display已经是合法且无损的JSON
+        _ => {
+            let _ = write!(out,"{}",value);
+        },
+    }
+    Ok(())
+}
+
+fn newParser<'a,'o>(buf: &'a [u8],opts: &'o ParseOptions,record: bool) -> jsonParser<'a,'o>{
     //先判定一下是否有BOM头
-    let mut realdata = &buf[..];
+    let mut start = 0;
     if buf.len() > 2 && buf[0] == 0xEF && buf[1] == 0xBB && buf[2] == 0xBF{ //BOM
-        realdata = &buf[3..];
+        start = 3;
+    }
+    jsonParser{
+        buf,
+        pos: start,
+        opts,
+        spans: if record { Some(Vec::new()) } else { None },
+        path: String::new(),
+        keyStart: None,
+        depth: 0,
+        rawOpts: None,
+        utf8Checked: false,
+    }
+}
+
+fn runParser(buf: &[u8],opts: &ParseOptions,record: bool) -> result::Result<(DxValue,Option<SourceMap>),errorJson>{
+    checkInputSize(buf.len(),opts)?;
+    let (text,enc) = encoding::decode(buf,opts.encoding)?;
+    runDecoded(buf,&text,enc,opts,record)
+}
+
+/// 解析转码后的文本，出错位置换算回原始输入，位置表中是转码后文本的位置
+fn runDecoded(buf: &[u8],text: &[u8],enc: InputEncoding,opts: &ParseOptions,record: bool) -> result::Result<(DxValue,Option<SourceMap>),errorJson>{
+    //decode已经校验过(或转码得到)合法的UTF-8
+    match runParserUtf8(text,opts,record,true) {
+        Ok((value,spans)) => Ok((value,spans.map(|spans| SourceMap::build(text,spans)))),
+        Err(e) if enc != InputEncoding::Utf8 => {
+            let pos = encoding::originalPos(buf,text,e.pos() as usize,enc);
+            Err(e.with_pos(pos))
+        },
+        Err(e) => Err(e),
+    }
+}
+
+fn runParserUtf8(buf: &[u8],opts: &ParseOptions,record: bool,utf8Checked: bool) -> result::Result<(DxValue,Option<Vec<NodeSpan>>),errorJson>{
+    let mut parser = newParser(buf,opts,record);
+    parser.utf8Checked = utf8Checked;
+    parser.skipWhite();
+    let value = parser.parseValue()?;
+    parser.skipWhite();
+    if parser.pos < buf.len(){
+        return Err(parser.unexpected(errJsonReson::JET_Invalidate));
+    }
+    Ok((value,parser.spans))
+}
+
+struct jsonParser<'a,'o>{
+    buf: &'a [u8],
+    pos: usize,
+    opts: &'o ParseOptions,
+    spans: Option<Vec<NodeSpan>>,   //为None时不记录位置
+    path: String,                   //当前节点的JSON Pointer，只在记录位置时维护
+    keyStart: Option<usize>,        //即将解析的值所属键的起始位置
+    depth: usize,                   //当前容器的嵌套深度
+    rawOpts: Option<Arc<ParseOptions>>, //延迟节点共享的解析选项
+    utf8Checked: bool,              //整个输入已经校验过UTF-8，字符串不再逐段校验
+}
+
+impl<'a,'o> jsonParser<'a,'o>{
+    fn error(&self,reson: errJsonReson) -> errorJson{
+        errorJson::new(self.pos as isize,reson)
+    }
+
+    /// 当前位置不是期望的字符时，尽量给出具体原因(如严格模式下的注释、单引号)
+    fn unexpected(&self,fallback: errJsonReson) -> errorJson{
+        let next = self.buf.get(self.pos + 1).copied();
+        let reson = match self.peek() {
+            Some(b'/') if next == Some(b'/') || next == Some(b'*') => errJsonReson::JET_Comment,
+            Some(b'\'') => errJsonReson::JET_SingleQuote,
+            _ => fallback,
+        };
+        self.error(reson)
+    }
+
+    /// 按duplicate_keys检查新的键：返回Ok(Some(i))表示与第i个成员重复
+    fn duplicateKey<'k>(&self,keys: &mut keyIndex,key: &str,keyStart: usize,len: usize,keyAt: impl Fn(usize) -> &'k str) -> result::Result<Option<usize>,errorJson>{
+        if self.opts.duplicate_keys == DuplicateKeys::KeepAll{
+            return Ok(None);
+        }
+        match keys.lookup(key,len,keyAt) {
+            Some(_) if self.opts.duplicate_keys == DuplicateKeys::Error => Err(errorJson::new(keyStart as isize,errJsonReson::JET_DuplicateKey)),
+            dup => Ok(dup),
+        }
+    }
+
+    /// 进入一层对象/数组
+    fn enter(&mut self) -> result::Result<(),errorJson>{
+        if self.opts.max_depth > 0 && self.depth >= self.opts.max_depth{
+            return Err(self.error(errJsonReson::JET_TooDeep));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn checkContainerLen(&self,len: usize) -> result::Result<(),errorJson>{
+        if self.opts.max_container_len > 0 && len >= self.opts.max_container_len{
+            return Err(self.error(errJsonReson::JET_ContainerTooLarge));
+        }
+        Ok(())
+    }
+
+    fn checkStringLen(&self,len: usize,start: usize) -> result::Result<(),errorJson>{
+        if self.opts.max_string_len > 0 && len > self.opts.max_string_len{
+            return Err(errorJson::new(start as isize,errJsonReson::JET_StringTooLong));
+        }
+        Ok(())
+    }
+
+    fn allowComments(&self) -> bool{
+        self.opts.allow_comments || self.opts.json5
+    }
+
+    fn allowTrailingCommas(&self) -> bool{
+        self.opts.allow_trailing_commas || self.opts.json5
+    }
+
+    fn skipWhite(&mut self){
+        loop {
+            let (_,skiplen) = skipWB(&self.buf[self.pos..]);
+            self.pos += skiplen;
+            if self.opts.json5{
+                //JSON5额外的空白：\v \f U+00A0 U+FEFF U+2028 U+2029
+                let n = match &self.buf[self.pos..] {
+                    [0x0B | 0x0C,..] => 1,
+                    [0xC2,0xA0,..] => 2,
+                    [0xEF,0xBB,0xBF,..] | [0xE2,0x80,0xA8 | 0xA9,..] => 3,
+                    _ => 0,
+                };
+                if n > 0{
+                    self.pos += n;
+                    continue;
+                }
+            }
+            if !self.allowComments() || self.peek() != Some(b'/'){
+                return;
+            }
+            match self.buf.get(self.pos + 1) {
+                Some(b'/') => {
+                    match self.buf[self.pos..].iter().position(|&c| c == b'\n') {
+                        Some(n) => self.pos += n + 1,
+                        None => self.pos = self.buf.len(),
+                    }
+                },
+                Some(b'*') => {
+                    match self.buf[self.pos + 2..].windows(2).position(|w| w == b"*/") {
+                        Some(n) => self.pos += n + 4,
+                        None => return,  //未结束的块注释留给后续报错
+                    }
+                },
+                _ => return,
+            }
+        }
+    }
+
+    fn pushPath(&mut self,token: &str) -> usize{
+        let len = self.path.len();
+        if self.spans.is_some(){
+            self.path.push('/');
+            self.path.push_str(&escapePointerToken(token));
+        }
+        len
+    }
+
+    fn parseValue(&mut self) -> Result{
+        let keyStart = self.keyStart.take();
+        let start = self.pos;
+        let value = self.parseValueInner()?;
+        if let Some(spans) = &mut self.spans{
+            spans.push(NodeSpan{path: self.path.clone(),key_start: keyStart,start,end: self.pos});
+        }
+        Ok(value)
+    }
+
+    fn peek(&self) -> Option<u8>{
+        self.buf.get(self.pos).copied()
+    }
+
+    fn lazy(&self) -> bool{
+        self.opts.lazy_depth > 0 && self.depth >= self.opts.lazy_depth
+            && !self.opts.allow_comments && !self.opts.allow_trailing_commas && !self.opts.json5
+            && self.opts.duplicate_keys != DuplicateKeys::Error
+    }
+
+    /// 只校验不构造，保存为DxValue::Raw
+    fn parseRaw(&mut self) -> Result{
+        let start = self.pos;
+        self.skipValue()?;
+        let text = match scan::validateUtf8(&self.buf[start..self.pos]) {
+            Ok(text) => text,
+            Err(n) => return Err(errorJson::new((start + n) as isize,errJsonReson::JET_Invalidate)),
+        };
+        let parseOpts = self.opts;
+        let opts = self.rawOpts.get_or_insert_with(|| Arc::new(parseOpts.clone())).clone();
+        Ok(DxValue::Raw(DxRawJson::from_parts(text,opts)))
+    }
+
+    /// 按严格JSON语法跳过一个值，不分配内存(字符串没有转义时)
+    fn skipValue(&mut self) -> result::Result<(),errorJson>{
+        let (isObj,close,noBack) = match self.peek() {
+            Some(b'{') => (true,b'}',errJsonReson::JET_NoObjBack),
+            Some(b'[') => (false,b']',errJsonReson::JET_NoArrBack),
+            Some(b'"') => return self.parseStrRef().map(|_| ()),
+            Some(b'-') | Some(b'0'..=b'9') => return self.scanNumber().map(|_| ()),
+            _ => return self.parseValueInner().map(|_| ()),
+        };
+        self.enter()?;
+        self.pos += 1;
+        self.skipWhite();
+        if self.peek() == Some(close){
+            self.pos += 1;
+            self.depth -= 1;
+            return Ok(());
+        }
+        let mut count = 0;
+        loop {
+            self.skipWhite();
+            match self.peek() {
+                None => return Err(self.error(noBack)),
+                Some(c) if c == close => return Err(self.error(errJsonReson::JET_TrailingComma)),
+                _ => {},
+            }
+            if isObj{
+                match self.peek() {
+                    Some(b'"') => { self.parseStrRef()?; },
+                    Some(c) if isIdentStart(c) => return Err(self.error(errJsonReson::JET_UnquotedKey)),
+                    _ => return Err(self.unexpected(errJsonReson::JET_NoKeyStart)),
+                }
+                self.skipWhite();
+                if self.peek() != Some(b':'){
+                    return Err(self.unexpected(errJsonReson::JET_NoKVSplit));
+                }
+                self.pos += 1;
+                self.skipWhite();
+            }
+            self.checkContainerLen(count)?;
+            count += 1;
+            self.skipValue()?;
+            self.skipWhite();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(c) if c == close => {
+                    self.pos += 1;
+                    self.depth -= 1;
+                    return Ok(());
+                },
+                None => return Err(self.error(noBack)),
+                _ => return Err(self.unexpected(errJsonReson::JET_NoValueSplit)),
+            }
+        }
+    }
+
+    fn parseValueInner(&mut self) -> Result{
+        match self.peek() {
+            Some(b'{') | Some(b'[') if self.lazy() => self.parseRaw(),
+            Some(b'{') => {
+                self.enter()?;
+                let v = self.parseObject();
+                self.depth -= 1;
+                v
+            },
+            Some(b'[') => {
+                self.enter()?;
+                let v = self.parseArray();
+                self.depth -= 1;
+                v
+            },
+            Some(b'"') | Some(b'\'') => {
+                let s = self.parseString()?;
+                if self.opts.parse_datetime{
+                    if let Some(dt) = DxDateTime::parse_rfc3339(&s){
+                        return Ok(DxValue::DateTime(dt));
+                    }
+                }
+                Ok(DxValue::String(s))
+            },
+            Some(b'-') | Some(b'0'..=b'9') => self.parseNumber(),
+            Some(b'+') | Some(b'.') | Some(b'I') | Some(b'N') if self.opts.json5 => self.parseNumber(),
+            Some(b'I') if self.buf[self.pos..].starts_with(b"Infinity") => Err(self.error(errJsonReson::JET_NonFinite)),
+            Some(b'N') if self.buf[self.pos..].starts_with(b"NaN") => Err(self.error(errJsonReson::JET_NonFinite)),
+            Some(b'+') | Some(b'.') => Err(self.error(errJsonReson::JET_NumberFormat)),
+            Some(b't') => self.parseLiteral(b"true",DxValue::Boolean(true)),
+            Some(b'f') => self.parseLiteral(b"false",DxValue::Boolean(false)),
+            Some(b'n') => self.parseLiteral(b"null",DxValue::None),
+            _ => Err(self.unexpected(errJsonReson::JET_Invalidate)),
+        }
+    }
+
+    fn parseLiteral(&mut self,lit: &[u8],value: DxValue) -> Result{
+        if self.buf[self.pos..].starts_with(lit){
+            self.pos += lit.len();
+            return Ok(value);
+        }
+        Err(self.error(errJsonReson::JET_Invalidate))
+    }
+
+    /// JSON5无引号的键
+    fn parseIdent(&mut self) -> result::Result<String,errorJson>{
+        let start = self.pos;
+        while let Some(c) = self.peek(){
+            if !isIdentPart(c){
+                break;
+            }
+            self.pos += 1;
+        }
+        self.checkStringLen(self.pos - start,start)?;
+        match std::str::from_utf8(&self.buf[start..self.pos]) {
+            Ok(s) => Ok(s.to_string()),
+            Err(e) => Err(errorJson::new((start + e.valid_up_to()) as isize,errJsonReson::JET_Invalidate)),
+        }
+    }
+
+    fn parseObject(&mut self) -> Result{
+        self.pos += 1;
+        let mut items: Vec<Key_Value> = Vec::new();
+        let mut keys = keyIndex::default();
+        self.skipWhite();
+        if self.peek() == Some(b'}'){
+            self.pos += 1;
+            return Ok(DxValue::Object(Arc::new(items)));
+        }
+        loop {
+            self.skipWhite();
+            let keyStart = self.pos;
+            let key = match self.peek() {
+                Some(b'"') | Some(b'\'') => self.parseString()?,
+                Some(c) if isIdentStart(c) => {
+                    if !self.opts.json5{
+                        return Err(self.error(errJsonReson::JET_UnquotedKey));
+                    }
+                    self.parseIdent()?
+                },
+                //空对象已在前面处理，这里的}一定跟在逗号后面
+                Some(b'}') => {
+                    if !self.allowTrailingCommas(){
+                        return Err(self.error(errJsonReson::JET_TrailingComma));
+                    }
+                    self.pos += 1;
+                    return Ok(DxValue::Object(Arc::new(items)));
+                },
+                None => return Err(self.error(errJsonReson::JET_NoObjBack)),
+                _ => return Err(self.unexpected(errJsonReson::JET_NoKeyStart)),
+            };
+            let dup = self.duplicateKey(&mut keys,&key,keyStart,items.len(),|i| items[i].Key.as_str())?;
+            self.skipWhite();
+            if self.peek() != Some(b':'){
+                return Err(self.unexpected(errJsonReson::JET_NoKVSplit));
+            }
+            self.pos += 1;
+            self.skipWhite();
+            let pathLen = self.pushPath(&key);
+            self.keyStart = Some(keyStart);
+            let mut value = self.parseValue()?;
+            self.path.truncate(pathLen);
+            if let DxValue::String(s) = &value{
+                if self.opts.base64_keys.contains(&key){
+                    if let Some(bin) = base64::decode(s){
+                        value = DxValue::Binary(bin);
+                    }
+                }
+            }
+            match dup {
+                Some(i) if self.opts.duplicate_keys == DuplicateKeys::Last => items[i].Value = value,
+                Some(_) => {},
+                None => {
+                    self.checkContainerLen(items.len())?;
+                    items.push(Key_Value::new_value(&key,value));
+                },
+            }
+            self.skipWhite();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(DxValue::Object(Arc::new(items)));
+                },
+                None => return Err(self.error(errJsonReson::JET_NoObjBack)),
+                _ => return Err(self.unexpected(errJsonReson::JET_NoValueSplit)),
+            }
+        }
+    }
+
+    fn parseArray(&mut self) -> Result{
+        self.pos += 1;
+        let mut items: Vec<DxValue> = Vec::new();
+        self.skipWhite();
+        if self.peek() == Some(b']'){
+            self.pos += 1;
+            return Ok(DxValue::Array(Arc::new(items)));
+        }
+        loop {
+            self.skipWhite();
+            match self.peek() {
+                None => return Err(self.error(errJsonReson::JET_NoArrBack)),
+                Some(b']') => {
+                    if !self.allowTrailingCommas(){
+                        return Err(self.error(errJsonReson::JET_TrailingComma));
+                    }
+                    self.pos += 1;
+                    return Ok(DxValue::Array(Arc::new(items)));
+                },
+                _ => {},
+            }
+            let pathLen = self.pushPath(&items.len().to_string());
+            self.checkContainerLen(items.len())?;
+            items.push(self.parseValue()?);
+            self.path.truncate(pathLen);
+            self.skipWhite();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(DxValue::Array(Arc::new(items)));
+                },
+                None => return Err(self.error(errJsonReson::JET_NoArrBack)),
+                _ => return Err(self.unexpected(errJsonReson::JET_NoValueSplit)),
+            }
+        }
+    }
+
+    fn parseHex(&mut self,digits: usize) -> result::Result<u32,errorJson>{
+        if self.pos + digits > self.buf.len(){
+            return Err(self.error(errJsonReson::JET_NoStrEnd));
+        }
+        let mut n = 0u32;
+        for _ in 0..digits{
+            let d = match self.buf[self.pos] {
+                c @ b'0'..=b'9' => c - b'0',
+                c @ b'a'..=b'f' => c - b'a' + 10,
+                c @ b'A'..=b'F' => c - b'A' + 10,
+                _ => return Err(self.error(errJsonReson::JET_BadEscape)),
+            };
+            n = n << 4 | d as u32;
+            self.pos += 1;
+        }
+        Ok(n)
+    }
+
+    fn pushChar(&self,out: &mut Vec<u8>,code: u32) -> result::Result<(),errorJson>{
+        match std::char::from_u32(code) {
+            Some(ch) => {
+                let mut tmp = [0u8;4];
+                out.extend_from_slice(ch.encode_utf8(&mut tmp).as_bytes());
+                Ok(())
+            },
+            None => Err(self.error(errJsonReson::JET_BadEscape)),
+        }
+    }
+
+    /// 解析双引号字符串，JSON5模式下也接受单引号
+    fn parseString(&mut self) -> result::Result<String,errorJson>{
+        let quote = match self.peek() {
+            Some(b'"') => b'"',
+            Some(b'\'') if self.opts.json5 => b'\'',
+            Some(b'\'') => return Err(self.error(errJsonReson::JET_SingleQuote)),
+            _ => return Err(self.error(errJsonReson::JET_NoStrStart)),
+        };
+        let strStart = self.pos;
+        self.pos += 1;
+        let mut out: Vec<u8> = Vec::new();
+        let mut start = self.pos;
+        loop {
+            match self.peek() {
+                None => return Err(self.error(errJsonReson::JET_NoStrEnd)),
+                Some(c) if c == quote => {
+                    self.copySegment(&mut out,start)?;
+                    self.checkStringLen(out.len(),strStart)?;
+                    self.pos += 1;
+                    break;
+                },
+                Some(b'\\') => {
+                    self.copySegment(&mut out,start)?;
+                    self.checkStringLen(out.len(),strStart)?;
+                    self.pos += 1;
+                    let c = match self.peek() {
+                        None => return Err(self.error(errJsonReson::JET_NoStrEnd)),
+                        Some(c) => c,
+                    };
+                    self.pos += 1;
+                    match c {
+                        b'"' => out.push(b'"'),
+                        b'\\' => out.push(b'\\'),
+                        b'/' => out.push(b'/'),
+                        b'b' => out.push(0x08),
+                        b'f' => out.push(0x0C),
+                        b'n' => out.push(b'\n'),
+                        b'r' => out.push(b'\r'),
+                        b't' => out.push(b'\t'),
+                        b'u' => {
+                            let mut code = self.parseHex(4)?;
+                            if (0xD800..0xDC00).contains(&code){
+                                //高位代理，后面必须跟着低位代理
+                                if !self.buf[self.pos..].starts_with(b"\\u"){
+                                    return Err(self.error(errJsonReson::JET_BadEscape));
+                                }
+                                self.pos += 2;
+                                let low = self.parseHex(4)?;
+                                if !(0xDC00..0xE000).contains(&low){
+                                    return Err(self.error(errJsonReson::JET_BadEscape));
+                                }
+                                code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                            }
+                            self.pushChar(&mut out,code)?;
+                        },
+                        //以下为JSON5的转义
+                        b'\'' if self.opts.json5 => out.push(b'\''),
+                        b'v' if self.opts.json5 => out.push(0x0B),
+                        b'0' if self.opts.json5 && !matches!(self.peek(),Some(b'0'..=b'9')) => out.push(0),
+                        b'x' if self.opts.json5 => {
+                            let code = self.parseHex(2)?;
+                            self.pushChar(&mut out,code)?;
+                        },
+                        //续行：反斜杠后面紧跟换行
+                        b'\n' if self.opts.json5 => {},
+                        b'\r' if self.opts.json5 => {
+                            if self.peek() == Some(b'\n'){
+                                self.pos += 1;
+                            }
+                        },
+                        0xE2 if self.opts.json5 && matches!(self.buf[self.pos..],[0x80,0xA8 | 0xA9,..]) => self.pos += 2,
+                        //其它非数字字符表示它自己，多字节字符留给下一段
+                        c if self.opts.json5 && c >= 0x80 => self.pos -= 1,
+                        c if self.opts.json5 && !c.is_ascii_digit() => out.push(c),
+                        _ => {
+                            self.pos -= 1;
+                            return Err(self.error(errJsonReson::JET_BadEscape));
+                        },
+                    }
+                    start = self.pos;
+                },
+                Some(c) if c < 0x20 => return Err(self.error(errJsonReson::JET_ControlChar)),
+                //直接跳到下一个需要处理的字符
+                Some(_) => self.pos += 1 + scan::findStringSpecial(&self.buf[self.pos + 1..],quote),
+            }
+        }
+        //每一段原文都已校验，转义产生的总是合法UTF-8
+        Ok(unsafe { String::from_utf8_unchecked(out) })
+    }
+
+    /// 把start到当前位置的原文追加到out，逐段校验UTF-8，出错位置是原文中的位置。
+    /// 段的边界是引号或反斜杠，不会切开多字节字符
+    fn copySegment(&self,out: &mut Vec<u8>,start: usize) -> result::Result<(),errorJson>{
+        let seg = &self.buf[start..self.pos];
+        if !self.utf8Checked{
+            if let Err(n) = scan::validateUtf8(seg){
+                return Err(errorJson::new((start + n) as isize,errJsonReson::JET_Invalidate));
+            }
+        }
+        out.extend_from_slice(seg);
+        Ok(())
+    }
+
+    fn digitsFrom(&self,mut i: usize) -> usize{
+        while i < self.buf.len() && self.buf[i].is_ascii_digit(){
+            i += 1;
+        }
+        i
     }
-    let (buf,skiplen) = skipWB(&buf);
 
-    Result::Err(errorJson{errPos:0,reson: errJsonReson::JET_Invalidate})
-}
\ No newline at end of file
+    /// 按JSON数字语法扫描，返回数字文本和是否为整数
+    fn scanNumber(&mut self) -> result::Result<(&'a str,bool),errorJson>{
+        let begin = self.pos;
+        let buf = self.buf;
+        let mut integer = true;
+        if self.peek() == Some(b'-'){
+            self.pos += 1;
+        }
+        match self.peek() {
+            Some(b'0') => {
+                self.pos += 1;
+                match self.peek() {
+                    Some(b'x') | Some(b'X') => return Err(errorJson::new(begin as isize,errJsonReson::JET_HexNumber)),
+                    Some(b'0'..=b'9') => return Err(self.error(errJsonReson::JET_NumberFormat)),
+                    _ => {},
+                }
+            },
+            Some(b'1'..=b'9') => self.pos = self.digitsFrom(self.pos),
+            Some(b'I') if buf[self.pos..].starts_with(b"Infinity") => return Err(self.error(errJsonReson::JET_NonFinite)),
+            _ => return Err(self.error(errJsonReson::JET_NumberFormat)),
+        }
+        if self.peek() == Some(b'.'){
+            integer = false;
+            self.pos += 1;
+            if !matches!(self.peek(),Some(b'0'..=b'9')){
+                return Err(self.error(errJsonReson::JET_NumberFormat));
+            }
+            self.pos = self.digitsFrom(self.pos);
+        }
+        if let Some(b'e') | Some(b'E') = self.peek(){
+            integer = false;
+            self.pos += 1;
+            if let Some(b'+') | Some(b'-') = self.peek(){
+                self.pos += 1;
+            }
+            if !matches!(self.peek(),Some(b'0'..=b'9')){
+                return Err(self.error(errJsonReson::JET_NumberFormat));
+            }
+            self.pos = self.digitsFrom(self.pos);
+        }
+        //数字只包含ASCII字符
+        let text = std::str::from_utf8(&buf[begin..self.pos]).unwrap_or_default();
+        Ok((text,integer))
+    }
+
+    /// 处理JSON5才有的数字写法：正号、Infinity/NaN、十六进制、前导/末尾小数点。
+    /// 标准JSON数字返回None，交给scanNumber
+    fn scanJson5Number(&mut self) -> result::Result<Option<json5Number>,errorJson>{
+        let begin = self.pos;
+        let b = self.buf;
+        let mut i = self.pos;
+        let sign = match b.get(i) {
+            Some(&c) if c == b'+' || c == b'-' => {
+                i += 1;
+                Some(c)
+            },
+            _ => None,
+        };
+        let negative = sign == Some(b'-');
+        let rest = &b[i..];
+        if rest.starts_with(b"Infinity"){
+            self.pos = i + 8;
+            return Ok(Some(json5Number::Value(DxValue::Double(if negative { f64::NEG_INFINITY } else { f64::INFINITY }))));
+        }
+        if rest.starts_with(b"NaN"){
+            self.pos = i + 3;
+            return Ok(Some(json5Number::Value(DxValue::Double(f64::NAN))));
+        }
+        if rest.starts_with(b"0x") || rest.starts_with(b"0X"){
+            let digits = i + 2;
+            self.pos = digits;
+            while let Some(c) = self.peek(){
+                if !c.is_ascii_hexdigit(){
+                    break;
+                }
+                self.pos += 1;
+            }
+            if self.pos == digits{
+                return Err(self.error(errJsonReson::JET_NumberFormat));
+            }
+            let hex = std::str::from_utf8(&b[digits..self.pos]).unwrap_or_default();
+            let n = match u128::from_str_radix(hex,16) {
+                Ok(n) => n,
+                Err(_) => return Err(errorJson::new(begin as isize,errJsonReson::JET_NumberRange)),
+            };
+            let text = if negative { format!("-{}",n) } else { n.to_string() };
+            return Ok(Some(json5Number::Text(text,true)));
+        }
+        let intStart = i;
+        i = self.digitsFrom(i);
+        let intEnd = i;
+        let mut fracEnd = i;
+        if b.get(i) == Some(&b'.'){
+            fracEnd = self.digitsFrom(i + 1);
+            i = fracEnd;
+        }
+        let hasInt = intEnd > intStart;
+        let hasFrac = fracEnd > intEnd + 1;
+        if sign != Some(b'+') && hasInt && (fracEnd == intEnd || hasFrac){
+            return Ok(None);
+        }
+        if !hasInt && !hasFrac{
+            self.pos = i;
+            return Err(self.error(errJsonReson::JET_NumberFormat));
+        }
+        if intEnd - intStart > 1 && b[intStart] == b'0'{
+            self.pos = intStart + 1;
+            return Err(self.error(errJsonReson::JET_NumberFormat));
+        }
+        //规范成标准JSON数字文本
+        let mut text = String::new();
+        if negative{
+            text.push('-');
+        }
+        if hasInt{
+            text.push_str(std::str::from_utf8(&b[intStart..intEnd]).unwrap_or_default());
+        }else{
+            text.push('0');
+        }
+        let mut integer = !hasFrac;
+        if hasFrac{
+            text.push_str(std::str::from_utf8(&b[intEnd..fracEnd]).unwrap_or_default());
+        }
+        if let Some(b'e') | Some(b'E') = b.get(i){
+            integer = false;
+            text.push('e');
+            i += 1;
+            if let Some(&c) = b.get(i).filter(|c| **c == b'+' || **c == b'-'){
+                text.push(c as char);
+                i += 1;
+            }
+            let expEnd = self.digitsFrom(i);
+            if expEnd == i{
+                self.pos = i;
+                return Err(self.error(errJsonReson::JET_NumberFormat));
+            }
+            text.push_str(std::str::from_utf8(&b[i..expEnd]).unwrap_or_default());
+            i = expEnd;
+        }
+        self.pos = i;
+        Ok(Some(json5Number::Text(text,integer)))
+    }
+
+    fn parseNumber(&mut self) -> Result{
+        let begin = self.pos;
+        if self.opts.json5{
+            match self.scanJson5Number()? {
+                Some(json5Number::Value(v)) => return Ok(v),
+                Some(json5Number::Text(text,integer)) => return self.numberValue(&text,integer,begin),
+                None => {},
+            }
+        }
+        let (text,integer) = self.scanNumber()?;
+        self.numberValue(text,integer,begin)
+    }
+
+    fn numberValue(&self,text: &str,integer: bool,begin: usize) -> Result{
+        if self.opts.keep_number_text{
+            return Ok(DxValue::RawNumber(text.to_string()));
+        }
+        if self.opts.decimal && !integer{
+            return match text.parse::<DxDecimal>() {
+                Ok(v) => Ok(DxValue::Decimal(v)),
+                Err(_) => Err(errorJson::new(begin as isize,errJsonReson::JET_NumberRange)),
+            };
+        }
+        //整数按能容纳的最小范围选择类型，都放不下时才退化为Double
+        match numberFromText(text) {
+            Some(v) => Ok(v),
+            None => Err(self.error(errJsonReson::JET_Invalidate)),
+        }
+    }
+    /// 没有转义时直接借用输入，否则退回parseString
+    fn parseStrRef(&mut self) -> result::Result<Cow<'a,str>,errorJson>{
+        let buf = self.buf;
+        if self.peek() == Some(b'"'){
+            let start = self.pos + 1;
+            let end = start + scan::findStringSpecial(&buf[start..],b'"');
+            if buf.get(end) == Some(&b'"'){
+                self.checkStringLen(end - start,self.pos)?;
+                if let Ok(s) = scan::validateUtf8(&buf[start..end]){
+                    self.pos = end + 1;
+                    return Ok(Cow::Borrowed(s));
+                }
+            }
+        }
+        self.parseString().map(Cow::Owned)
+    }
+
+    fn parseValueRef(&mut self) -> result::Result<DxValueRef<'a>,errorJson>{
+        match self.peek() {
+            Some(b'{') | Some(b'[') => {
+                self.enter()?;
+                let v = if self.peek() == Some(b'{') { self.parseObjectRef() } else { self.parseArrayRef() };
+                self.depth -= 1;
+                v
+            },
+            Some(b'"') | Some(b'\'') => self.parseStrRef().map(DxValueRef::String),
+            Some(b'-') | Some(b'0'..=b'9') | Some(b'+') | Some(b'.') | Some(b'I') | Some(b'N') if self.opts.json5 => {
+                match self.scanJson5Number()? {
+                    Some(json5Number::Text(text,_)) => Ok(DxValueRef::Number(Cow::Owned(text))),
+                    Some(json5Number::Value(DxValue::Double(d))) => {
+                        let text = if d.is_nan() { "NaN" } else if d > 0.0 { "Infinity" } else { "-Infinity" };
+                        Ok(DxValueRef::Number(Cow::Borrowed(text)))
+                    },
+                    Some(json5Number::Value(_)) => Err(self.error(errJsonReson::JET_Invalidate)),
+                    None => Ok(DxValueRef::Number(Cow::Borrowed(self.scanNumber()?.0))),
+                }
+            },
+            Some(b'-') | Some(b'0'..=b'9') => Ok(DxValueRef::Number(Cow::Borrowed(self.scanNumber()?.0))),
+            Some(b't') => self.parseLiteral(b"true",DxValue::None).map(|_| DxValueRef::Boolean(true)),
+            Some(b'f') => self.parseLiteral(b"false",DxValue::None).map(|_| DxValueRef::Boolean(false)),
+            Some(b'n') => self.parseLiteral(b"null",DxValue::None).map(|_| DxValueRef::None),
+            //其它情况的错误原因与parseValue一致
+            _ => self.parseValueInner().map(|_| DxValueRef::None),
+        }
+    }
+
+    fn parseObjectRef(&mut self) -> result::Result<DxValueRef<'a>,errorJson>{
+        self.pos += 1;
+        let mut items: Vec<(Cow<'a,str>,DxValueRef<'a>)> = Vec::new();
+        let mut keys = keyIndex::default();
+        self.skipWhite();
+        if self.peek() == Some(b'}'){
+            self.pos += 1;
+            return Ok(DxValueRef::Object(items));
+        }
+        loop {
+            self.skipWhite();
+            let keyStart = self.pos;
+            let key = match self.peek() {
+                Some(b'"') | Some(b'\'') => self.parseStrRef()?,
+                Some(c) if isIdentStart(c) => {
+                    if !self.opts.json5{
+                        return Err(self.error(errJsonReson::JET_UnquotedKey));
+                    }
+                    let start = self.pos;
+                    self.parseIdent()?;
+                    Cow::Borrowed(std::str::from_utf8(&self.buf[start..self.pos]).unwrap_or_default())
+                },
+                Some(b'}') => {
+                    if !self.allowTrailingCommas(){
+                        return Err(self.error(errJsonReson::JET_TrailingComma));
+                    }
+                    self.pos += 1;
+                    return Ok(DxValueRef::Object(items));
+                },
+                None => return Err(self.error(errJsonReson::JET_NoObjBack)),
+                _ => return Err(self.unexpected(errJsonReson::JET_NoKeyStart)),
+            };
+            let dup = self.duplicateKey(&mut keys,&key,keyStart,items.len(),|i| items[i].0.as_ref())?;
+            self.skipWhite();
+            if self.peek() != Some(b':'){
+                return Err(self.unexpected(errJsonReson::JET_NoKVSplit));
+            }
+            self.pos += 1;
+            self.skipWhite();
+            let value = self.parseValueRef()?;
+            match dup {
+                Some(i) if self.opts.duplicate_keys == DuplicateKeys::Last => items[i].1 = value,
+                Some(_) => {},
+                None => {
+                    self.checkContainerLen(items.len())?;
+                    items.push((key,value));
+                },
+            }
+            self.skipWhite();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(DxValueRef::Object(items));
+                },
+                None => return Err(self.error(errJsonReson::JET_NoObjBack)),
+                _ => return Err(self.unexpected(errJsonReson::JET_NoValueSplit)),
+            }
+        }
+    }
+
+    fn parseArrayRef(&mut self) -> result::Result<DxValueRef<'a>,errorJson>{
+        self.pos += 1;
+        let mut items: Vec<DxValueRef<'a>> = Vec::new();
+        self.skipWhite();
+        if self.peek() == Some(b']'){
+            self.pos += 1;
+            return Ok(DxValueRef::Array(items));
+        }
+        loop {
+            self.skipWhite();
+            match self.peek() {
+                None => return Err(self.error(errJsonReson::JET_NoArrBack)),
+                Some(b']') => {
+                    if !self.allowTrailingCommas(){
+                        return Err(self.error(errJsonReson::JET_TrailingComma));
+                    }
+                    self.pos += 1;
+                    return Ok(DxValueRef::Array(items));
+                },
+                _ => {},
+            }
+            self.checkContainerLen(items.len())?;
+            items.push(self.parseValueRef()?);
+            self.skipWhite();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(DxValueRef::Array(items));
+                },
+                None => return Err(self.error(errJsonReson::JET_NoArrBack)),
+                _ => return Err(self.unexpected(errJsonReson::JET_NoValueSplit)),
+            }
+        }
+    }
+}
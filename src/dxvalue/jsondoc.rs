@@ -0,0 +1,283 @@
+//! 保留格式的JSON/JSONC文档：记住原文的空白、注释和键顺序，
+//! 通过setKeyValue等修改时只改写受影响的那一段文本，保存后的差异尽量小
+
+use std::fmt::{Display, Formatter};
+use std::{fs, io};
+use crate::dxvalue::{DxValue, writeJsonString};
+use crate::dxvalue::json::{self, errJsonReson, errorJson, NodeSpan, ParseOptions};
+
+pub struct JsonDocument{
+    text: String,
+    root: DxValue,
+    spans: Vec<NodeSpan>,
+    opts: ParseOptions,
+}
+
+//跳过空白和注释，返回下一个有效字符的位置
+fn skipTrivia(b: &[u8],mut i: usize) -> usize{
+    while i < b.len(){
+        match b[i] {
+            b' ' | b'\t' | b'\r' | b'\n' => i += 1,
+            b'/' if b.get(i + 1) == Some(&b'/') => {
+                while i < b.len() && b[i] != b'\n'{
+                    i += 1;
+                }
+            },
+            b'/' if b.get(i + 1) == Some(&b'*') => {
+                match b[i + 2..].windows(2).position(|w| w == b"*/") {
+                    Some(n) => i += n + 4,
+                    None => return i,
+                }
+            },
+            _ => break,
+        }
+    }
+    i
+}
+
+//一行中只有空白和注释
+fn onlyTrivia(line: &str) -> bool{
+    skipTrivia(line.as_bytes(),0) == line.len()
+}
+
+//从i开始第一个不在块注释中的换行，到end为止没有时返回None
+fn lineEnd(b: &[u8],mut i: usize,end: usize) -> Option<usize>{
+    while i < end{
+        match b[i] {
+            b'\n' => return Some(i),
+            b'/' if b.get(i + 1) == Some(&b'/') => return b[i..end].iter().position(|c| *c == b'\n').map(|n| i + n),
+            b'/' if b.get(i + 1) == Some(&b'*') => match b[i + 2..end].windows(2).position(|w| w == b"*/") {
+                Some(n) => i += n + 4,
+                None => return None,
+            },
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+fn lineStart(text: &str,pos: usize) -> usize{
+    text[..pos].rfind('\n').map_or(0,|n| n + 1)
+}
+
+/// 节点所在行在它前面只有空白时，返回这段缩进
+fn ownLineIndent(text: &str,pos: usize) -> Option<&str>{
+    let prefix = &text[lineStart(text,pos)..pos];
+    if prefix.trim().is_empty() { Some(prefix) } else { None }
+}
+
+fn splitPath(path: &str) -> Option<(&str,String)>{
+    let n = path.rfind('/')?;
+    Some((&path[..n],path[n + 1..].replace("~1","/").replace("~0","~")))
+}
+
+impl JsonDocument{
+    /// 解析JSON/JSONC文本，允许注释和末尾逗号
+    pub fn parse(text: &str) -> Result<Self,errorJson>{
+        let opts = ParseOptions::jsonc();
+        let (root,spans) = json::parseJsonSpans(text,&opts)?;
+        Ok(JsonDocument{text: text.to_string(),root,spans,opts})
+    }
+
+    pub fn load(file: &str) -> Result<Self,errorJson>{
+        match fs::read_to_string(file) {
+            Ok(text) => JsonDocument::parse(&text),
+            Err(_) => Err(errorJson::new(0,errJsonReson::JET_UnParse)),
+        }
+    }
+
+    pub fn save(&self,file: &str) -> io::Result<()>{
+        fs::write(file,&self.text)
+    }
+
+    pub fn root(&self) -> &DxValue{
+        &self.root
+    }
+
+    pub fn as_str(&self) -> &str{
+        &self.text
+    }
+
+    fn span(&self,path: &str) -> Option<&NodeSpan>{
+        self.spans.iter().find(|s| s.path == path)
+    }
+
+    /// 直接子节点的位置，按在原文中的先后排序
+    fn children(&self,parent: &str) -> Vec<&NodeSpan>{
+        let mut out: Vec<&NodeSpan> = self.spans.iter().filter(|s| {
+            s.path.len() > parent.len() && s.path.starts_with(parent)
+                && s.path.as_bytes()[parent.len()] == b'/' && !s.path[parent.len() + 1..].contains('/')
+        }).collect();
+        out.sort_by_key(|s| s.start);
+        out
+    }
+
+    /// 按从后往前的顺序替换若干区间，然后重新解析；结果不合法或者expect指定的路径不存在时恢复原文
+    fn splice(&mut self,mut edits: Vec<(usize,usize,String)>,expect: Option<&str>) -> bool{
+        edits.sort_by_key(|e| std::cmp::Reverse(e.0));
+        let old = self.text.clone();
+        for (start,end,text) in edits{
+            self.text.replace_range(start..end,&text);
+        }
+        match json::parseJsonSpans(&self.text,&self.opts) {
+            Ok((root,spans)) if !matches!(expect,Some(p) if root.get_byPointer(p).is_none()) => {
+                self.root = root;
+                self.spans = spans;
+                true
+            },
+            _ => {
+                self.text = old;
+                false
+            },
+        }
+    }
+
+    /// 设置path(JSON Pointer)处的值：已存在时只替换值的文本，
+    /// 不存在时追加到父对象末尾(或数组末尾)，沿用相邻成员的缩进和逗号风格。
+    /// 父节点不存在或不是容器、值含有NaN/Infinity时返回false
+    pub fn setKeyValue(&mut self,path: &str,value: DxValue) -> bool{
+        //数字按无损的JSON文本写入，不用Display
+        let text = match value.to_json() {
+            Ok(text) => text,
+            Err(_) => return false,
+        };
+        if let Some(sp) = self.span(path){
+            let edit = (sp.start,sp.end,text);
+            return self.splice(vec![edit],Some(path));
+        }
+        let (parent,key) = match splitPath(path) {
+            Some(v) => v,
+            None => return false,
+        };
+        let member = match self.root.get_byPointer(parent) {
+            Some(DxValue::Object(_)) => {
+                let mut m = String::new();
+                let _ = writeJsonString(&mut m,&key);
+                m.push_str(": ");
+                m.push_str(&text);
+                m
+            },
+            Some(DxValue::Array(arr)) if key.parse::<usize>().ok() == Some(arr.len()) => text,
+            _ => return false,
+        };
+        let edits = self.insertEdits(parent,member);
+        self.splice(edits,Some(path))
+    }
+
+    pub fn setKeyString(&mut self,path: &str,value: &str) -> bool{
+        self.setKeyValue(path,DxValue::String(value.to_string()))
+    }
+
+    fn insertEdits(&self,parent: &str,member: String) -> Vec<(usize,usize,String)>{
+        let b = self.text.as_bytes();
+        let ps = match self.span(parent) {
+            Some(sp) => sp,
+            None => return Vec::new(),
+        };
+        let close = ps.end - 1;
+        let children = self.children(parent);
+        let last = match children.last() {
+            Some(last) => *last,
+            None => {
+                //空容器：多行时换行并在父节点缩进的基础上再缩进两格
+                if self.text[ps.start..close].contains('\n'){
+                    let indent = &self.text[lineStart(&self.text,ps.start)..];
+                    let indent: String = indent.chars().take_while(|c| *c == ' ' || *c == '\t').collect();
+                    return vec![(ps.start + 1,ps.start + 1,format!("\n{}  {}",indent,member))];
+                }
+                return vec![(ps.start + 1,ps.start + 1,member)];
+            },
+        };
+        let after = skipTrivia(b,last.end);
+        let trailingComma = after < close && b[after] == b',';
+        let itemStart = last.key_start.unwrap_or(last.start);
+        match ownLineIndent(&self.text,itemStart) {
+            Some(indent) => {
+                //每个成员独占一行：新成员写在最后一个成员所在行(含行尾注释，块注释跨行时到注释结束那一行)的后面
+                let from = if trailingComma { after + 1 } else { last.end };
+                let item = if trailingComma { format!("{},",member) } else { member };
+                let mut edits = Vec::new();
+                match lineEnd(b,from,close) {
+                    Some(n) => edits.push((n,n,format!("\n{}{}",indent,item))),
+                    None => edits.push((close,close,format!("\n{}{}",indent,item))),
+                }
+                if !trailingComma{
+                    edits.push((last.end,last.end,",".to_string()));
+                }
+                edits
+            },
+            None if trailingComma => vec![(after + 1,after + 1,format!(" {},",member))],
+            None => vec![(last.end,last.end,format!(", {}",member))],
+        }
+    }
+
+    /// 删除path处的成员及其分隔逗号；成员独占一行时整行删除
+    pub fn removeKey(&mut self,path: &str) -> bool{
+        let b = self.text.as_bytes();
+        let sp = match self.span(path) {
+            Some(sp) => sp,
+            None => return false,
+        };
+        let parent = match splitPath(path) {
+            Some((parent,_)) => parent,
+            None => return false,
+        };
+        let itemStart = sp.key_start.unwrap_or(sp.start);
+        let siblings = self.children(parent);
+        let prev = siblings.iter().rev().find(|s| s.start < itemStart).map(|s| s.end);
+        let ownLine = ownLineIndent(&self.text,itemStart).is_some();
+        let after = skipTrivia(b,sp.end);
+        let mut edits = Vec::new();
+        if after < b.len() && b[after] == b','{
+            let mut end = after + 1;
+            if ownLine{
+                let rest = &self.text[end..];
+                let lineEnd = rest.find('\n').map_or(rest.len(),|n| n + 1);
+                if onlyTrivia(&rest[..lineEnd]){
+                    end += lineEnd;
+                }
+            }else{
+                while end < b.len() && b[end] == b' '{
+                    end += 1;
+                }
+            }
+            let start = if ownLine { lineStart(&self.text,itemStart) } else { itemStart };
+            edits.push((start,end,String::new()));
+        }else{
+            //最后一个成员：去掉前一个成员后面的逗号
+            if let Some(pe) = prev{
+                let comma = skipTrivia(b,pe);
+                if comma < itemStart && b[comma] == b','{
+                    edits.push((comma,comma + 1,String::new()));
+                }
+            }
+            let start = match (ownLine,prev) {
+                (true,_) => lineStart(&self.text,itemStart).saturating_sub(1).max(edits.first().map_or(0,|e| e.1)),
+                (false,Some(_)) if !edits.is_empty() => edits[0].1,
+                _ => itemStart,
+            };
+            //独占一行时连同行尾注释一起删除
+            let mut end = sp.end;
+            if ownLine{
+                let rest = &self.text[end..];
+                let lineEnd = rest.find('\n').unwrap_or(rest.len());
+                if onlyTrivia(&rest[..lineEnd]){
+                    end += lineEnd;
+                }
+            }
+            edits.push((start,end,String::new()));
+            //两个区间相邻时合并
+            if edits.len() == 2 && edits[0].1 == edits[1].0{
+                let first = edits.remove(0);
+                edits[0].0 = first.0;
+            }
+        }
+        self.splice(edits,None)
+    }
+}
+
+impl Display for JsonDocument{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.text)
+    }
+}
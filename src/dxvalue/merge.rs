@@ -0,0 +1,202 @@
+//! 三方合并：以base为共同祖先，自动合并ours和theirs中互不冲突的修改，
+//! 双方对同一位置做了不同修改时生成冲突，交给合并策略处理或留给调用方
+
+use std::sync::Arc;
+use crate::dxvalue::schema::jsonEqual;
+use crate::dxvalue::{escapePointerToken, DxValue, Key_Value};
+
+/// 某个位置上三方的值，None表示该处不存在(被删除或未添加)
+#[derive(Debug,Clone)]
+pub struct Conflict{
+    pub path: String,
+    pub base: Option<DxValue>,
+    pub ours: Option<DxValue>,
+    pub theirs: Option<DxValue>,
+}
+
+/// 对一个冲突的处理
+#[derive(Debug,Clone)]
+pub enum Resolution{
+    Ours,
+    Theirs,
+    Base,
+    /// 使用指定的值
+    Value(DxValue),
+    /// 删除该位置
+    Remove,
+    /// 不处理，保留为冲突，结果中暂时使用ours
+    Unresolved,
+}
+
+pub trait MergeStrategy{
+    fn resolve(&self,conflict: &Conflict) -> Resolution;
+}
+
+/// 所有冲突留给调用方
+pub struct KeepConflicts;
+
+pub struct PreferOurs;
+
+pub struct PreferTheirs;
+
+impl MergeStrategy for KeepConflicts{
+    fn resolve(&self,_: &Conflict) -> Resolution{
+        Resolution::Unresolved
+    }
+}
+
+impl MergeStrategy for PreferOurs{
+    fn resolve(&self,_: &Conflict) -> Resolution{
+        Resolution::Ours
+    }
+}
+
+impl MergeStrategy for PreferTheirs{
+    fn resolve(&self,_: &Conflict) -> Resolution{
+        Resolution::Theirs
+    }
+}
+
+impl<F: Fn(&Conflict) -> Resolution> MergeStrategy for F{
+    fn resolve(&self,conflict: &Conflict) -> Resolution{
+        self(conflict)
+    }
+}
+
+#[derive(Debug,Clone)]
+pub struct MergeResult{
+    pub value: DxValue,
+    /// 未解决的冲突，为空时合并成功
+    pub conflicts: Vec<Conflict>,
+}
+
+impl MergeResult{
+    pub fn is_clean(&self) -> bool{
+        self.conflicts.is_empty()
+    }
+}
+
+pub fn merge3(base: &DxValue,ours: &DxValue,theirs: &DxValue) -> MergeResult{
+    merge3_with(base,ours,theirs,&KeepConflicts)
+}
+
+/// 对象按键递归合并；数组在三方长度相同时按下标合并，双方都只在base末尾追加时合并追加的部分，
+/// 其它情况整个数组作为一个冲突
+pub fn merge3_with(base: &DxValue,ours: &DxValue,theirs: &DxValue,strategy: &dyn MergeStrategy) -> MergeResult{
+    let mut merger = Merger{strategy,conflicts: Vec::new()};
+    let value = merger.merge("",Some(base),Some(ours),Some(theirs)).unwrap_or(DxValue::None);
+    MergeResult{value,conflicts: merger.conflicts}
+}
+
+fn same(a: Option<&DxValue>,b: Option<&DxValue>) -> bool{
+    match (a,b) {
+        (Some(a),Some(b)) => jsonEqual(a,b),
+        (None,None) => true,
+        _ => false,
+    }
+}
+
+fn members(v: &DxValue) -> Option<&[Key_Value]>{
+    match v {
+        DxValue::Object(obj) => Some(obj),
+        _ => None,
+    }
+}
+
+fn items(v: &DxValue) -> Option<&[DxValue]>{
+    match v {
+        DxValue::Array(arr) => Some(arr),
+        _ => None,
+    }
+}
+
+struct Merger<'s>{
+    strategy: &'s dyn MergeStrategy,
+    conflicts: Vec<Conflict>,
+}
+
+impl Merger<'_>{
+    fn merge(&mut self,path: &str,base: Option<&DxValue>,ours: Option<&DxValue>,theirs: Option<&DxValue>) -> Option<DxValue>{
+        if same(ours,theirs) || same(base,theirs){
+            return ours.cloned();
+        }
+        if same(base,ours){
+            return theirs.cloned();
+        }
+        //双方都修改了，容器再深入一层
+        let (base,ours,theirs) = (base.map(DxValue::loaded),ours.map(DxValue::loaded),theirs.map(DxValue::loaded));
+        if let (Some(o),Some(t)) = (ours.and_then(members),theirs.and_then(members)){
+            //双方各自新建的对象按空的base合并
+            let b = base.map_or(Some(&[][..]),members);
+            if let Some(b) = b{
+                return Some(self.mergeObject(path,b,o,t));
+            }
+        }
+        if let (Some(b),Some(o),Some(t)) = (base.and_then(items),ours.and_then(items),theirs.and_then(items)){
+            if let Some(v) = self.mergeArray(path,b,o,t){
+                return Some(v);
+            }
+        }
+        self.conflict(path,base,ours,theirs)
+    }
+
+    fn conflict(&mut self,path: &str,base: Option<&DxValue>,ours: Option<&DxValue>,theirs: Option<&DxValue>) -> Option<DxValue>{
+        let c = Conflict{path: path.to_string(),base: base.cloned(),ours: ours.cloned(),theirs: theirs.cloned()};
+        match self.strategy.resolve(&c) {
+            Resolution::Ours => c.ours,
+            Resolution::Theirs => c.theirs,
+            Resolution::Base => c.base,
+            Resolution::Value(v) => Some(v),
+            Resolution::Remove => None,
+            Resolution::Unresolved => {
+                let v = c.ours.clone();
+                self.conflicts.push(c);
+                v
+            },
+        }
+    }
+
+    /// 结果中的键按ours的顺序，theirs新增的键排在后面
+    fn mergeObject(&mut self,path: &str,base: &[Key_Value],ours: &[Key_Value],theirs: &[Key_Value]) -> DxValue{
+        fn get<'v>(m: &'v [Key_Value],key: &str) -> Option<&'v DxValue>{
+            m.iter().find(|kv| kv.Key == key).map(|kv| &kv.Value)
+        }
+        let mut keys: Vec<&str> = Vec::new();
+        for kv in ours.iter().chain(theirs).chain(base){
+            if !keys.contains(&kv.Key.as_str()){
+                keys.push(&kv.Key);
+            }
+        }
+        let mut out = Vec::with_capacity(keys.len());
+        for key in keys{
+            let child = format!("{}/{}",path,escapePointerToken(key));
+            if let Some(v) = self.merge(&child,get(base,key),get(ours,key),get(theirs,key)){
+                out.push(Key_Value::new_value(key,v));
+            }
+        }
+        DxValue::Object(Arc::new(out))
+    }
+
+    fn mergeArray(&mut self,path: &str,base: &[DxValue],ours: &[DxValue],theirs: &[DxValue]) -> Option<DxValue>{
+        if base.len() == ours.len() && base.len() == theirs.len(){
+            //策略返回Remove时去掉该元素
+            let out = (0..base.len())
+                .filter_map(|i| self.merge(&format!("{}/{}",path,i),Some(&base[i]),Some(&ours[i]),Some(&theirs[i])))
+                .collect();
+            return Some(DxValue::Array(Arc::new(out)));
+        }
+        let prefix = |arr: &[DxValue]| arr.len() >= base.len() && arr.iter().zip(base).all(|(a,b)| jsonEqual(a,b));
+        if prefix(ours) && prefix(theirs){
+            let mut out = ours.to_vec();
+            let ourTail = &ours[base.len()..];
+            let theirTail = &theirs[base.len()..];
+            //双方追加了相同的内容时只保留一份
+            let same = ourTail.len() == theirTail.len() && ourTail.iter().zip(theirTail).all(|(a,b)| jsonEqual(a,b));
+            if !same{
+                out.extend_from_slice(theirTail);
+            }
+            return Some(DxValue::Array(Arc::new(out)));
+        }
+        None
+    }
+}
@@ -1,24 +1,43 @@
 pub mod json;
+pub mod base64;
+pub mod datetime;
+pub mod decimal;
+pub mod jsondoc;
+pub mod ndjson;
+pub mod parallel;
+pub mod valueref;
+pub mod raw;
+pub mod scan;
+pub mod encoding;
+pub mod sourcemap;
+pub mod canonical;
+pub mod sha256;
+pub mod schema;
+pub mod infer;
+pub mod codegen;
+pub mod diff;
+pub mod merge;
 
 use std::fmt::{Debug, Formatter, Result, Display};
-use std::cell;
-use std::cell::{RefCell, Ref};
 use std::str::FromStr;
-use std::any::TypeId;
-use std::borrow::Borrow;
-use std::ops::Div;
+use std::convert::TryFrom;
+use std::sync::Arc;
+pub use datetime::DxDateTime;
+pub use decimal::DxDecimal;
+pub use valueref::DxValueRef;
+pub use raw::DxRawJson;
 
-#[derive(Debug)]
+#[derive(Debug,Clone)]
 pub struct Key_Value{
     Key:    String,
-    Value:  cell::RefCell<DxValue>
+    Value:  DxValue
 }
 
 impl Key_Value{
     pub fn new_value(key: &str,v: DxValue) -> Self{
         Key_Value{
             Key:    key.to_string(),
-            Value:  cell::RefCell::new(v),
+            Value:  v,
         }
     }
 
@@ -50,43 +69,159 @@ impl Key_Value{
         Key_Value::new_value(key,DxValue::Double(v))
     }
 
+    pub fn new_binary(key: &str,v: &[u8]) -> Self{
+        Key_Value::new_value(key,DxValue::Binary(v.to_vec()))
+    }
+
+    pub fn new_datetime(key: &str,v: DxDateTime) -> Self{
+        Key_Value::new_value(key,DxValue::DateTime(v))
+    }
+
+    pub fn new_decimal(key: &str,v: DxDecimal) -> Self{
+        Key_Value::new_value(key,DxValue::Decimal(v))
+    }
+
 }
 
 impl Display for Key_Value{
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        write!(f,"\"{}\": {}",self.Key,self.Value.borrow())
+        writeJsonString(f,&self.Key)?;
+        write!(f,": {}",self.Value)
+    }
+}
+
+/// 按JSON规则输出带引号的字符串，转义引号、反斜杠和控制字符
+pub(crate) fn writeJsonString<W: std::fmt::Write>(f: &mut W,s: &str) -> Result{
+    f.write_char('"')?;
+    let mut start = 0;
+    for (i,c) in s.char_indices(){
+        let esc = match c {
+            '"' => "\\\"",
+            '\\' => "\\\\",
+            '\n' => "\\n",
+            '\r' => "\\r",
+            '\t' => "\\t",
+            '\u{08}' => "\\b",
+            '\u{0C}' => "\\f",
+            c if (c as u32) < 0x20 => "",
+            _ => continue,
+        };
+        f.write_str(&s[start..i])?;
+        if esc.is_empty(){
+            write!(f,"\\u{:04x}",c as u32)?;
+        }else{
+            f.write_str(esc)?;
+        }
+        start = i + c.len_utf8();
+    }
+    f.write_str(&s[start..])?;
+    f.write_char('"')
+}
+
+
+/// 把JSON数字文本转换为能容纳它的最小整数类型，都放不下或带小数/指数时转换为Double
+pub(crate) fn numberFromText(text: &str) -> Option<DxValue>{
+    if !text.contains(['.','e','E']){
+        if let Ok(v) = text.parse::<isize>(){
+            return Some(DxValue::Int(v));
+        }
+        if let Ok(v) = text.parse::<i64>(){
+            return Some(DxValue::Int64(v));
+        }
+        if let Ok(v) = text.parse::<u64>(){
+            return Some(DxValue::UInt64(v));
+        }
+        if let Ok(v) = text.parse::<i128>(){
+            return Some(DxValue::Int128(v));
+        }
+        if let Ok(v) = text.parse::<u128>(){
+            return Some(DxValue::UInt128(v));
+        }
     }
+    text.parse::<f64>().ok().map(DxValue::Double)
 }
 
+/// 按RFC 6901转义JSON Pointer中的一段：~ 写作 ~0，/ 写作 ~1
+pub(crate) fn escapePointerToken(token: &str) -> String{
+    token.replace('~',"~0").replace('/',"~1")
+}
 
-#[derive(Debug)]
+fn unescapePointerToken(token: &str) -> String{
+    token.replace("~1","/").replace("~0","~")
+}
+
+/// 按类型取值失败的原因
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum errValueReson{
+    VET_NotFound,       //没有对应的键或索引
+    VET_TypeMismatch,   //值的类型不能转换为目标类型
+    VET_Overflow,       //数值超出目标类型的范围
+    VET_NonFinite,      //NaN/Infinity不能输出为JSON
+}
+
+/// 万能值，容器不使用内部可变性，因此是 Send + Sync 的，可以放在 Arc 中跨线程共享。
+/// Object/Array 的子节点通过 Arc 共享，clone 只增加引用计数，
+/// 通过 set*/value_by* 修改时才会复制被修改的那一层(写时复制)，不会影响其他持有者
+#[derive(Debug,Clone)]
 pub enum  DxValue{
-    Object(cell::RefCell<Vec<Key_Value>>),
-    Array(cell::RefCell<Vec<DxValue>>),
+    Object(Arc<Vec<Key_Value>>),
+    Array(Arc<Vec<DxValue>>),
     String(String),
     Int(isize),
     Int32(i32),
     Int64(i64),
+    /// 超出i64范围的无符号整数，如snowflake id、哈希值
+    UInt64(u64),
+    Int128(i128),
+    UInt128(u128),
     Float(f32),
     Double(f64),
+    /// 精确的十进制数(金额等)，原样输出，不做浮点舍入
+    Decimal(DxDecimal),
+    /// 保留原始文本的JSON数字，如 1.10、1e400，输出时原样写回，取值时按需转换
+    RawNumber(String),
     Boolean(bool),
+    /// 二进制数据，输出JSON时编码为base64字符串
+    Binary(Vec<u8>),
+    /// 日期时间，输出JSON时为RFC 3339字符串
+    DateTime(DxDateTime),
+    /// 已校验但未解析的JSON对象/数组，输出时原样写回；
+    /// 通过value_byName/value_byIndex取到或者调用set*修改时展开；只读的get_by*/xx_byName/len
+    /// 在第一次访问时解析并缓存，查询结果与不延迟时相同
+    Raw(DxRawJson),
     None
 }
+/// NaN/Infinity按JSON5的写法输出，以JSON5模式能解析回来；严格JSON输出见json::toJson
+fn writeFloat(f: &mut Formatter<'_>,v: f64) -> Result{
+    if v.is_nan(){
+        f.write_str("NaN")
+    }else if v.is_infinite(){
+        f.write_str(if v > 0.0 { "Infinity" } else { "-Infinity" })
+    }else{
+        write!(f,"{:.2}",v)
+    }
+}
+
 impl Display for DxValue{
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
         match self {
-            DxValue::String(T)=> write!(f,"\"{}\"",T),
+            DxValue::String(T)=> writeJsonString(f,T),
             DxValue::Int(T) => write!(f,"{}",T),
             DxValue::Int32(T)=> write!(f,"{}",T),
             DxValue::Int64(T)=> write!(f,"{}",T),
-            DxValue::Float(T)=> write!(f,"{:.2}",T),
-            DxValue::Double(T)=> write!(f,"{:.2}",T),
+            DxValue::UInt64(T)=> write!(f,"{}",T),
+            DxValue::Int128(T)=> write!(f,"{}",T),
+            DxValue::UInt128(T)=> write!(f,"{}",T),
+            DxValue::Float(T)=> writeFloat(f,*T as f64),
+            DxValue::Double(T)=> writeFloat(f,*T),
+            DxValue::Decimal(T)=> write!(f,"{}",T),
+            DxValue::RawNumber(T)=> f.write_str(T),
             DxValue::Boolean(T)=> write!(f,"{}",T),
             DxValue::Object(T) =>{
                 write!(f,"{}","{")?; //默认不格式化
-                let vecObj = &(*T.borrow());
+                let vecObj = T;
                 let mut isfirst = true;
-                for obj in vecObj{
+                for obj in vecObj.iter(){
                     if isfirst{
                         write!(f,"{}",obj)?;
                         isfirst = false;
@@ -98,9 +233,9 @@ impl Display for DxValue{
             },
             DxValue::Array(T) =>{
                 write!(f,"[")?;
-                let vecArr = &(*T.borrow());
+                let vecArr = T;
                 let mut isfirst = true;
-                for v in vecArr{
+                for v in vecArr.iter(){
                     if isfirst{
                         write!(f,"{}",v)?;
                         isfirst = false;
@@ -110,7 +245,10 @@ impl Display for DxValue{
                 }
                 write!(f,"]")
             },
-            DxValue::None => write!(f,"Null"),
+            DxValue::Binary(T)=> write!(f,"\"{}\"",base64::encode(T)),
+            DxValue::DateTime(T)=> write!(f,"\"{}\"",T),
+            DxValue::Raw(T)=> write!(f,"{}",T),
+            DxValue::None => write!(f,"null"),
         }
     }
 }
@@ -119,31 +257,64 @@ impl DxValue {
     /// 创建一个KV结构
     /// # Examples
     /// ```
-    /// use rust_value::dxvalue::{Key_Value,DxValue};
-    /// let objMap = DxValue::newObject();
+    /// use rvalue::dxvalue::{Key_Value,DxValue};
+    /// let mut objMap = DxValue::newObject();
     /// objMap.setKeyString("name","不得闲");
     /// ```
     pub fn newObject() -> DxValue{
-        DxValue::Object(RefCell::new(Vec::new()))
+        DxValue::Object(Arc::new(Vec::new()))
     }
 
     pub fn newArray() -> DxValue{
-        DxValue::Array(RefCell::new(Vec::new()))
+        DxValue::Array(Arc::new(Vec::new()))
     }
 
     pub fn as_String(&self) -> String{
         format!("{}",self)
     }
 
+    /// 无损的紧凑JSON文本，见json::toJson
+    pub fn to_json(&self) -> std::result::Result<String,errValueReson>{
+        json::toJson(self)
+    }
+
+    /// RFC 8785规范化JSON，键有序、数字格式固定，相同内容得到相同字节
+    pub fn to_canonical(&self) -> std::result::Result<String,errValueReson>{
+        canonical::canonicalize(self)
+    }
+
+    /// 规范化JSON的SHA-256，与键的插入顺序、数字的存储类型无关
+    pub fn content_hash(&self) -> std::result::Result<[u8; 32],errValueReson>{
+        canonical::contentHash(self)
+    }
+
+    /// 与other比较，返回带路径的变更列表；对象忽略键的顺序，数组按下标比较
+    pub fn diff(&self,other: &DxValue) -> Vec<diff::Change>{
+        diff::diff(self,other,&diff::DiffOptions::default())
+    }
+
+    pub fn diff_with(&self,other: &DxValue,opts: &diff::DiffOptions) -> Vec<diff::Change>{
+        diff::diff(self,other,opts)
+    }
+
     /*pub fn iter(&self) -> Iter {
         Iter{curindex:0,ptr: Rc::new(self)}
     }*/
 
+    /// 超出isize范围的整数按符号取isize::MIN/MAX，与浮点数的转换一致；需要知道是否溢出时使用try_as_integer
     pub fn as_int(&self)->isize{
         match self {
             DxValue::Int(T)=> return *T,
             DxValue::Int32(T)=>return *T as isize,
-            DxValue::String(T)=> return (*T).parse::<isize>().unwrap_or(0),
+            DxValue::Int64(_) | DxValue::UInt64(_) | DxValue::Int128(_) | DxValue::UInt128(_) | DxValue::Decimal(_) | DxValue::String(_) =>{
+                return match self.try_as_integer::<isize>() {
+                    Ok(v) => v,
+                    Err(errValueReson::VET_Overflow) if self.as_double() < 0.0 => isize::MIN,
+                    Err(errValueReson::VET_Overflow) => isize::MAX,
+                    Err(_) => 0,
+                }
+            },
+            DxValue::RawNumber(T)=> return numberFromText(T).map_or(0,|v| v.as_int()),
             DxValue::Boolean(T)=> {
                 if *T{ return 1;}
             },
@@ -154,6 +325,110 @@ impl DxValue {
         0
     }
 
+    /// 检查范围后转换为指定的整数类型，超出范围返回VET_Overflow而不是截断；
+    /// 字符串按十进制解析，布尔值为0/1，浮点数不做隐式转换
+    pub fn try_as_integer<I>(&self) -> std::result::Result<I,errValueReson>
+        where I: TryFrom<i128> + TryFrom<u128>{
+        let wide: i128 = match self {
+            DxValue::Int(t) => *t as i128,
+            DxValue::Int32(t) => *t as i128,
+            DxValue::Int64(t) => *t as i128,
+            DxValue::UInt64(t) => *t as i128,
+            DxValue::Int128(t) => *t,
+            DxValue::UInt128(t) => return I::try_from(*t).map_err(|_| errValueReson::VET_Overflow),
+            DxValue::Boolean(t) => *t as i128,
+            DxValue::Decimal(t) => match t.to_i128() {
+                Some(v) => v,
+                //指数非负一定是整数，转换失败只能是溢出；否则是有小数部分，不做截断
+                None if t.exp() >= 0 => return Err(errValueReson::VET_Overflow),
+                None => return Err(errValueReson::VET_TypeMismatch),
+            },
+            //带小数或指数的数字与Double一样不做隐式转换，整数文本按字符串解析
+            DxValue::RawNumber(t) if t.contains(['.','e','E']) =>{
+                return Err(errValueReson::VET_TypeMismatch)
+            },
+            DxValue::String(t) | DxValue::RawNumber(t) => {
+                match t.parse::<i128>() {
+                    Ok(v) => v,
+                    Err(_) => {
+                        if let Ok(v) = t.parse::<u128>(){
+                            return I::try_from(v).map_err(|_| errValueReson::VET_Overflow);
+                        }
+                        //全是数字但超出128位时也算溢出
+                        let digits = t.strip_prefix('-').or_else(|| t.strip_prefix('+')).unwrap_or(t);
+                        if !digits.is_empty() && digits.bytes().all(|c| c.is_ascii_digit()){
+                            return Err(errValueReson::VET_Overflow);
+                        }
+                        return Err(errValueReson::VET_TypeMismatch);
+                    },
+                }
+            },
+            _ => return Err(errValueReson::VET_TypeMismatch),
+        };
+        I::try_from(wide).map_err(|_| errValueReson::VET_Overflow)
+    }
+
+    /// 通过名称查找对应的值(只读)，Array按下标字符串查找
+    pub fn get_byName(&self,name: &str) -> Option<&Self>{
+        match self.loaded() {
+            DxValue::Object(T) => T.iter().find(|obj| obj.Key == name).map(|obj| &obj.Value),
+            DxValue::Array(T) => name.parse::<usize>().ok().and_then(|index| T.get(index)),
+            _ => None,
+        }
+    }
+
+    /// 取出对象中所有名为name的值(解析时duplicate_keys为KeepAll才会有多个)，按出现顺序
+    pub fn values_byName(&self,name: &str) -> Vec<&Self>{
+        match self.loaded() {
+            DxValue::Object(T) => T.iter().filter(|obj| obj.Key == name).map(|obj| &obj.Value).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// 按JSON Pointer(RFC 6901)查找节点，如 /server/ports/0，空串表示自身
+    pub fn get_byPointer(&self,pointer: &str) -> Option<&Self>{
+        if pointer.is_empty(){
+            return Some(self);
+        }
+        let mut cur = self;
+        for token in pointer.strip_prefix('/')?.split('/'){
+            cur = cur.get_byName(&unescapePointerToken(token))?;
+        }
+        Some(cur)
+    }
+
+    pub fn get_byIndex(&self,index: usize) -> Option<&Self>{
+        match self.loaded() {
+            DxValue::Object(T) => T.get(index).map(|obj| &obj.Value),
+            DxValue::Array(T) => T.get(index),
+            _ => None,
+        }
+    }
+
+    /// 是Raw时解析展开成Object/Array，解析失败保持不变并返回false
+    pub fn expandRaw(&mut self) -> bool{
+        if let DxValue::Raw(raw) = self{
+            match raw.value() {
+                Some(v) => *self = v.clone(),
+                None => return false,
+            }
+        }
+        true
+    }
+
+    /// 只读访问时Raw使用解析缓存，节点本身不展开；解析失败时返回Raw本身
+    pub(crate) fn loaded(&self) -> &Self{
+        match self {
+            DxValue::Raw(raw) => raw.value().unwrap_or(self),
+            _ => self,
+        }
+    }
+
+    fn expanded(&mut self) -> &mut Self{
+        self.expandRaw();
+        self
+    }
+
     pub fn as_value<T: FromStr>(&self,defValue: T) -> T{
         //判定一下T是什么类型
         format!("{}",self).parse::<T>().unwrap_or(defValue)
@@ -163,10 +438,15 @@ impl DxValue {
         match self {
             DxValue::Boolean(t)=> *t,
             DxValue::Int64(t) => *t>0,
+            DxValue::UInt64(t) => *t>0,
+            DxValue::Int128(t) => *t>0,
+            DxValue::UInt128(t) => *t>0,
             DxValue::Int(t) => *t>0,
             DxValue::Int32(t) => *t>0,
             DxValue::Float(t) => *t>0.0,
             DxValue::Double(t) => *t>0.0,
+            DxValue::Decimal(t) => t.coeff()>0,
+            DxValue::RawNumber(t) => t.parse::<f64>().is_ok_and(|v| v>0.0),
             DxValue::String(t)=>{
                 t.to_lowercase().eq("true")
             }
@@ -178,11 +458,17 @@ impl DxValue {
         match self {
             DxValue::Int(T)=> return *T as f32,
             DxValue::Int32(T)=>return *T as f32,
+            DxValue::Int64(T)=>return *T as f32,
+            DxValue::UInt64(T)=>return *T as f32,
+            DxValue::Int128(T)=>return *T as f32,
+            DxValue::UInt128(T)=>return *T as f32,
             DxValue::String(T)=> return (*T).parse::<f32>().unwrap_or(0.0),
+            DxValue::RawNumber(T)=> return (*T).parse::<f32>().unwrap_or(0.0),
             DxValue::Boolean(T)=> {
                 if *T{ return 1.0;}
             },
             DxValue::Double(T) => return *T as f32,
+            DxValue::Decimal(T) => return T.to_f64() as f32,
             DxValue::Float(T) => return *T,
             _=>{}
         }
@@ -193,17 +479,62 @@ impl DxValue {
         match self {
             DxValue::Int(T)=> return *T as f64,
             DxValue::Int32(T)=>return *T as f64,
+            DxValue::Int64(T)=>return *T as f64,
+            DxValue::UInt64(T)=>return *T as f64,
+            DxValue::Int128(T)=>return *T as f64,
+            DxValue::UInt128(T)=>return *T as f64,
             DxValue::String(T)=> return (*T).parse::<f64>().unwrap_or(0 as f64),
+            DxValue::RawNumber(T)=> return (*T).parse::<f64>().unwrap_or(0 as f64),
             DxValue::Boolean(T)=> {
                 if *T{ return 1 as f64;}
             },
             DxValue::Double(T) => return *T,
+            DxValue::Decimal(T) => return T.to_f64(),
             DxValue::Float(T) => return *T as f64,
             _=>{}
         }
         0 as f64
     }
 
+    /// 获取二进制内容，String会尝试按base64解码，失败返回空
+    pub fn as_binary(&self) -> Vec<u8>{
+        match self {
+            DxValue::Binary(T) => T.clone(),
+            DxValue::String(T) => base64::decode(T).unwrap_or_default(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// 获取日期时间，String按RFC 3339解析，整数按Unix秒处理
+    pub fn as_datetime(&self) -> Option<DxDateTime>{
+        match self {
+            DxValue::DateTime(T) => Some(*T),
+            DxValue::String(T) => DxDateTime::parse_rfc3339(T),
+            DxValue::Int(T) => Some(DxDateTime::from_unix_secs(*T as i64)),
+            DxValue::Int32(T) => Some(DxDateTime::from_unix_secs(*T as i64)),
+            DxValue::Int64(T) => Some(DxDateTime::from_unix_secs(*T)),
+            _ => None,
+        }
+    }
+
+    /// 获取精确十进制值，整数精确转换，String按十进制文本解析，
+    /// 浮点数取能还原它的最短十进制表示
+    pub fn as_decimal(&self) -> Option<DxDecimal>{
+        match self {
+            DxValue::Decimal(T) => Some(*T),
+            DxValue::Int(T) => Some(DxDecimal::from_i128(*T as i128)),
+            DxValue::Int32(T) => Some(DxDecimal::from_i128(*T as i128)),
+            DxValue::Int64(T) => Some(DxDecimal::from_i128(*T as i128)),
+            DxValue::UInt64(T) => Some(DxDecimal::from_i128(*T as i128)),
+            DxValue::Int128(T) => Some(DxDecimal::from_i128(*T)),
+            DxValue::UInt128(T) => i128::try_from(*T).ok().map(DxDecimal::from_i128),
+            DxValue::Float(T) => DxDecimal::from_f64(*T as f64),
+            DxValue::Double(T) => DxDecimal::from_f64(*T),
+            DxValue::String(T) | DxValue::RawNumber(T) => T.parse().ok(),
+            _ => None,
+        }
+    }
+
     pub fn setString(&mut self,value: &str){
         match self {
             DxValue::String(T)=>{
@@ -240,35 +571,42 @@ impl DxValue {
         }
     }
 
+    pub fn setBinary(&mut self,value: &[u8]){
+        match self {
+            DxValue::Binary(T)=>{
+                T.clear();
+                T.extend_from_slice(value);
+            },
+            _=>{
+                *self = DxValue::Binary(value.to_vec())
+            }
+        }
+    }
+
+    pub fn setDecimal(&mut self,value: DxDecimal){
+        *self = DxValue::Decimal(value)
+    }
+
+    pub fn setDateTime(&mut self,value: DxDateTime){
+        *self = DxValue::DateTime(value)
+    }
+
     pub fn setKeyString(&mut self,name: &str,value: &str){
+        self.expandRaw();
         match self {
             DxValue::Object(T) =>{
-               /* let mut temp = T.borrow_mut();
-                let mut index = 0;
-                while index < temp.len(){
-                    if let Some(obj) = temp.get_mut(index){
-                        if obj.Key == name{
-                            let mut v = obj.Value.borrow_mut();
-                            *v = DxValue::String(value.to_string());
-                            return
-                        }
-                    }
-                    index += 1;
-                }
-                temp.push(Key_Value::new_value(name,DxValue::String(value.to_string())));*/
-                let vecobj = T.get_mut();
-                for obj in vecobj{
+                let T = Arc::make_mut(T);
+                for obj in T.iter_mut(){
                     if obj.Key == name{
-                        let mut v = obj.Value.borrow_mut();
-                        *v = DxValue::String(value.to_string());
+                        obj.Value = DxValue::String(value.to_string());
                         return
                     }
                 }
-                T.borrow_mut().push(Key_Value::new_value(name,DxValue::String(value.to_string())));
+                T.push(Key_Value::new_value(name,DxValue::String(value.to_string())));
             },
             DxValue::Array(T)=>{
                 if let Ok(index) = name.parse::<usize>(){
-                    let mut vecobj = T.get_mut();
+                    let vecobj = Arc::make_mut(T);
                     if index < 0{
                         vecobj.insert(0,DxValue::String(value.to_string()));
                         return;
@@ -287,20 +625,20 @@ impl DxValue {
     }
 
     pub fn setIndexValue(&mut self,idx: isize,value: Self){
+        self.expandRaw();
         match self {
             DxValue::Object(T) =>{
-                let mut vecobj = T.get_mut();
-                if idx < 0 || idx as usize >= vecobj.len(){
+                if idx < 0 || idx as usize >= T.len(){
                     return;
                 }
+                let vecobj = Arc::make_mut(T);
                 if let Some(obj) = vecobj.get_mut(idx as usize){
-                    let mut v = obj.Value.borrow_mut();
-                    *v = value;
+                    obj.Value = value;
                     return;
                 }
             },
             DxValue::Array(T)=>{
-                let vecobj = T.get_mut();
+                let vecobj = Arc::make_mut(T);
                 if idx < 0{
                     vecobj.insert(0,value);
                     return;
@@ -318,21 +656,21 @@ impl DxValue {
     }
 
     pub fn setKeyValue(& mut  self,name: &str,value: Self){
+        self.expandRaw();
         match self {
             DxValue::Object(T) =>{
-                let mut vecobj = T.get_mut();
-                for obj in vecobj{
+                let T = Arc::make_mut(T);
+                for obj in T.iter_mut(){
                     if obj.Key == name{
-                        let mut v = obj.Value.borrow_mut();
-                        *v = value;
+                        obj.Value = value;
                         return
                     }
                 }
-                T.borrow_mut().push(Key_Value::new_value(name,value));
+                T.push(Key_Value::new_value(name,value));
             },
             DxValue::Array(T)=>{
                 if let Ok(index) = name.parse::<usize>(){
-                    let vecobj = T.get_mut();
+                    let vecobj = Arc::make_mut(T);
                     if index < 0{
                         vecobj.insert(0,value);
                         return;
@@ -351,12 +689,12 @@ impl DxValue {
     }
 
     pub fn len(&self)->usize{
-        match self {
+        match self.loaded() {
             DxValue::Object(t)=>{
-                return t.borrow().len();
+                return t.len();
             },
             DxValue::Array(t)=>{
-                return t.borrow().len();
+                return t.len();
             },
             _=>(),
         }
@@ -364,21 +702,21 @@ impl DxValue {
     }
 
     pub fn string_byName(&self,name: &str,defValue: &str) -> String{
-        match self {
+        match self.loaded() {
             DxValue::Object(T) =>{
-                let vec = &(*T.borrow());
+                let vec = T;
                 for i in 0..vec.len(){
-                    let v = vec[i].borrow();
+                    let v = &vec[i];
                     if v.Key == name{
-                        return v.Value.borrow().as_String();
+                        return v.Value.as_String();
                     }
                 }
             },
             DxValue::Array(T) =>{
                 if let Ok(index) = name.parse::<usize>(){
-                    let arr = &(*T.borrow());
+                    let arr = T;
                     if index >= 0 && index < arr.len(){
-                        return arr[index].borrow().as_String();
+                        return arr[index].as_String();
                     }
                 }
             },
@@ -387,62 +725,36 @@ impl DxValue {
         defValue.to_string()
     }
 
+    /// 整数值超出isize范围时返回defValue，需要区分原因时使用try_int_byName
     pub fn int_byName(&self,name: &str,defValue: isize) -> isize{
-        match self {
-            DxValue::Object(T) =>{
-                let vec = &(*T.borrow());
-                for i in 0..vec.len(){
-                    let obj = vec[i].borrow();
-                    if obj.Key == name{
-                        match &(*obj.Value.borrow()) {
-                            DxValue::Int32(t) => return *t  as isize,
-                            DxValue::Int(t) => return *t,
-                            DxValue::Int64(t) => return *t as isize,
-                            DxValue::String(t) => return (*t).parse::<isize>().unwrap_or(defValue),
-                            DxValue::Boolean(t) =>{
-                                if *t {return 1;}
-                                else {return 0;}
-                            }
-                            _=> return defValue,
-                        }
-                    }
-                }
-            },
-            DxValue::Array(T) =>{
-                if let Ok(index) = name.parse::<usize>(){
-                    let arr = &(*T.borrow());
-                    if index >= 0 && index < arr.len(){
-                        match &(*arr[index].borrow()){
-                            DxValue::Int32(t) => return *t  as isize,
-                            DxValue::Int(t) => return *t,
-                            DxValue::Int64(t) => return *t as isize,
-                            DxValue::String(t) => return (*t).parse::<isize>().unwrap_or(defValue),
-                            DxValue::Boolean(t) =>{
-                                if *t {return 1;}
-                                else {return 0;}
-                            }
-                            _=> return defValue,
-                        }
-                    }
-                }
-            },
-            _=>(),
+        self.try_int_byName(name).unwrap_or(defValue)
+    }
+
+    pub fn try_int_byName(&self,name: &str) -> std::result::Result<isize,errValueReson>{
+        match self.get_byName(name) {
+            Some(v) => v.try_as_integer::<isize>(),
+            None => Err(errValueReson::VET_NotFound),
         }
-        defValue
     }
 
     pub fn float_byName(&self,name: &str,defValue: f32) -> f32{
-        match self {
+        match self.loaded() {
             DxValue::Object(T) =>{
-                let vec = &(*T.borrow());
+                let vec = T;
                 for i in 0..vec.len(){
-                    let obj = vec[i].borrow();
+                    let obj = &vec[i];
                     if obj.Key == name{
-                        match &(*obj.Value.borrow()) {
+                        match &obj.Value {
                             DxValue::Int32(t) => return *t  as f32,
                             DxValue::Int(t) => return *t as f32,
                             DxValue::Int64(t) => return *t as f32,
-                            DxValue::String(t) => return (*t).parse::<f32>().unwrap_or(defValue),
+                            DxValue::UInt64(t) => return *t as f32,
+                            DxValue::Int128(t) => return *t as f32,
+                            DxValue::UInt128(t) => return *t as f32,
+                            DxValue::Float(t) => return *t,
+                            DxValue::Double(t) => return *t as f32,
+                            DxValue::Decimal(t) => return t.to_f64() as f32,
+                            DxValue::String(t) | DxValue::RawNumber(t) => return (*t).parse::<f32>().unwrap_or(defValue),
                             DxValue::Boolean(t) =>{
                                 if *t {return 1 as f32;}
                                 else {return 0 as f32;}
@@ -454,13 +766,19 @@ impl DxValue {
             },
             DxValue::Array(T) =>{
                 if let Ok(index) = name.parse::<usize>(){
-                    let arr = &(*T.borrow());
+                    let arr = T;
                     if index >= 0 && index < arr.len(){
-                        match &(*arr[index].borrow()){
+                        match &arr[index]{
                             DxValue::Int32(t) => return *t  as f32,
                             DxValue::Int(t) => return *t as f32,
                             DxValue::Int64(t) => return *t as f32,
-                            DxValue::String(t) => return (*t).parse::<f32>().unwrap_or(defValue),
+                            DxValue::UInt64(t) => return *t as f32,
+                            DxValue::Int128(t) => return *t as f32,
+                            DxValue::UInt128(t) => return *t as f32,
+                            DxValue::Float(t) => return *t,
+                            DxValue::Double(t) => return *t as f32,
+                            DxValue::Decimal(t) => return t.to_f64() as f32,
+                            DxValue::String(t) | DxValue::RawNumber(t) => return (*t).parse::<f32>().unwrap_or(defValue),
                             DxValue::Boolean(t) =>{
                                 if *t {return 1 as f32;}
                                 else {return 0 as f32;}
@@ -475,18 +793,26 @@ impl DxValue {
         defValue
     }
 
+    /// Decimal通过DxDecimal::to_f64显式转换为最接近的f64，可能丢失精度，
+    /// 需要精确值时使用decimal_byName
     pub fn double_byName(&self,name: &str,defValue: f64) -> f64{
-        match self {
+        match self.loaded() {
             DxValue::Object(T) =>{
-                let vec = &(*T.borrow());
+                let vec = T;
                 for i in 0..vec.len(){
-                    let obj = vec[i].borrow();
+                    let obj = &vec[i];
                     if obj.Key == name{
-                        match &(*obj.Value.borrow()) {
+                        match &obj.Value {
                             DxValue::Int32(t) => return *t  as f64,
                             DxValue::Int(t) => return *t as f64,
                             DxValue::Int64(t) => return *t as f64,
-                            DxValue::String(t) => return (*t).parse::<f64>().unwrap_or(defValue),
+                            DxValue::UInt64(t) => return *t as f64,
+                            DxValue::Int128(t) => return *t as f64,
+                            DxValue::UInt128(t) => return *t as f64,
+                            DxValue::Float(t) => return *t as f64,
+                            DxValue::Double(t) => return *t,
+                            DxValue::Decimal(t) => return t.to_f64(),
+                            DxValue::String(t) | DxValue::RawNumber(t) => return (*t).parse::<f64>().unwrap_or(defValue),
                             DxValue::Boolean(t) =>{
                                 if *t {return 1 as f64;}
                                 else {return 0 as f64;}
@@ -498,13 +824,19 @@ impl DxValue {
             },
             DxValue::Array(T) =>{
                 if let Ok(index) = name.parse::<usize>(){
-                    let arr = &(*T.borrow());
+                    let arr = T;
                     if index >= 0 && index < arr.len(){
-                        match &(*arr[index].borrow()){
+                        match &arr[index]{
                             DxValue::Int32(t) => return *t  as f64,
                             DxValue::Int(t) => return *t as f64,
                             DxValue::Int64(t) => return *t as f64,
-                            DxValue::String(t) => return (*t).parse::<f64>().unwrap_or(defValue),
+                            DxValue::UInt64(t) => return *t as f64,
+                            DxValue::Int128(t) => return *t as f64,
+                            DxValue::UInt128(t) => return *t as f64,
+                            DxValue::Float(t) => return *t as f64,
+                            DxValue::Double(t) => return *t,
+                            DxValue::Decimal(t) => return t.to_f64(),
+                            DxValue::String(t) | DxValue::RawNumber(t) => return (*t).parse::<f64>().unwrap_or(defValue),
                             DxValue::Boolean(t) =>{
                                 if *t {return 1 as f64;}
                                 else {return 0 as f64;}
@@ -520,17 +852,20 @@ impl DxValue {
     }
 
     pub fn bool_byName(&self,name: &str,defValue: bool) -> bool{
-        match self {
+        match self.loaded() {
             DxValue::Object(T) =>{
-                let vec = &(*T.borrow());
+                let vec = T;
                 for i in 0..vec.len(){
-                    let obj = vec[i].borrow();
+                    let obj = &vec[i];
                     if obj.Key == name{
-                        match &(*obj.Value.borrow()) {
+                        match &obj.Value {
                             DxValue::Int32(t) => return *t != 0,
                             DxValue::Int(t) => return *t != 0,
                             DxValue::Int64(t) => return *t != 0,
-                            DxValue::String(t) => {
+                            DxValue::UInt64(t) => return *t != 0,
+                            DxValue::Int128(t) => return *t != 0,
+                            DxValue::UInt128(t) => return *t != 0,
+                            DxValue::String(t) | DxValue::RawNumber(t) => {
                                 if let Ok(v64) = (*t).parse::<f64>(){
                                     return v64 != 0.0;
                                 }
@@ -543,13 +878,16 @@ impl DxValue {
             },
             DxValue::Array(T) =>{
                 if let Ok(index) = name.parse::<usize>(){
-                    let arr = &(*T.borrow());
+                    let arr = T;
                     if index >= 0 && index < arr.len(){
-                        match &(*arr[index].borrow()){
+                        match &arr[index]{
                             DxValue::Int32(t) => return *t != 0,
                             DxValue::Int(t) => return *t != 0,
                             DxValue::Int64(t) => return *t != 0,
-                            DxValue::String(t) => {
+                            DxValue::UInt64(t) => return *t != 0,
+                            DxValue::Int128(t) => return *t != 0,
+                            DxValue::UInt128(t) => return *t != 0,
+                            DxValue::String(t) | DxValue::RawNumber(t) => {
                                 if let Ok(v64) = (*t).parse::<f64>(){
                                     return v64 != 0.0;
                                 }
@@ -565,22 +903,68 @@ impl DxValue {
         defValue
     }
 
+    pub fn binary_byName(&self,name: &str) -> Vec<u8>{
+        match self.loaded() {
+            DxValue::Object(T) =>{
+                for obj in T.iter(){
+                    if obj.Key == name{
+                        return obj.Value.as_binary();
+                    }
+                }
+            },
+            DxValue::Array(T) =>{
+                if let Ok(index) = name.parse::<usize>(){
+                    if index < T.len(){
+                        return T[index].as_binary();
+                    }
+                }
+            },
+            _=>(),
+        }
+        Vec::new()
+    }
+
+    pub fn decimal_byName(&self,name: &str) -> Option<DxDecimal>{
+        self.get_byName(name).and_then(|v| v.as_decimal())
+    }
+
+    pub fn datetime_byName(&self,name: &str) -> Option<DxDateTime>{
+        match self.loaded() {
+            DxValue::Object(T) =>{
+                for obj in T.iter(){
+                    if obj.Key == name{
+                        return obj.Value.as_datetime();
+                    }
+                }
+            },
+            DxValue::Array(T) =>{
+                if let Ok(index) = name.parse::<usize>(){
+                    if index < T.len(){
+                        return T[index].as_datetime();
+                    }
+                }
+            },
+            _=>(),
+        }
+        None
+    }
+
     pub fn num_byName<T: Display+std::str::FromStr>(&self,name: &str,defValue: T) -> T{
-        match self {
+        match self.loaded() {
             DxValue::Object(T) =>{
-                let vec = &(*T.borrow());
-                for vecobj in vec{
-                    let obj = vecobj.borrow();
+                let vec = T;
+                for vecobj in vec.iter(){
+                    let obj = vecobj;
                     if obj.Key == name{
-                        return format!("{}",obj.Value.borrow()).parse::<T>().unwrap_or(defValue);
+                        return format!("{}",obj.Value).parse::<T>().unwrap_or(defValue);
                     }
                 }
             },
             DxValue::Array(T) =>{
                 if let Ok(index) = name.parse::<usize>(){
-                    let arr = &(*T.borrow());
+                    let arr = T;
                     if index >= 0 && index < arr.len(){
-                        let v = arr[index].borrow();
+                        let v = &arr[index];
                         return format!("{}",v).parse::<T>().unwrap_or(defValue);
                     }
                 }
@@ -593,25 +977,22 @@ impl DxValue {
     /// 通过名称查找对应的值
     /// # Examples
     /// ```
-    /// use rust_value::dxvalue::{Key_Value,DxValue};
+    /// use rvalue::dxvalue::{Key_Value,DxValue};
     ///
     /// ```
     pub fn value_byName(&mut self, name: &str) ->Option<&mut Self> {
+        self.expandRaw();
         match self {
             DxValue::Object(T) =>{
-                let objects = T.get_mut();
-                for i in 0..objects.len(){
-                    if objects[i].Key == name{
-                        return Some(objects[i].Value.get_mut());
-                    }
+                if let Some(i) = T.iter().position(|obj| obj.Key == name){
+                    return Some(Arc::make_mut(T)[i].Value.expanded());
                 }
             },
             DxValue::Array(T) =>{
                 //将字符串转换为整数
                 if let Ok(index) = name.parse::<usize>(){
-                    let arr = T.get_mut();
-                    if index >= 0 && index < arr.len(){
-                        return Some(& mut arr[index]);
+                    if index < T.len(){
+                        return Some(Arc::make_mut(T)[index].expanded());
                     }
                 }
             },
@@ -623,17 +1004,16 @@ impl DxValue {
     /// 查询指定的索引位置上的值
     ///
     pub fn value_byIndex<'a>(&'a mut self,index: usize) -> Option<&'a mut Self>{
+        self.expandRaw();
         match self {
             DxValue::Object(T) =>{
-                let vec = T.get_mut();
-                if index < vec.len(){
-                    return Some(vec[index].Value.get_mut());
+                if index < T.len(){
+                    return Some(Arc::make_mut(T)[index].Value.expanded());
                 }
             },
             DxValue::Array(T)=>{
-                let arr = T.get_mut();
-                if index < arr.len(){
-                    return Some(&mut arr[index]);
+                if index < T.len(){
+                    return Some(Arc::make_mut(T)[index].expanded());
                 }
             },
             _=>(),
@@ -642,17 +1022,17 @@ impl DxValue {
     }
 
     pub fn string_byIndex(&self,index: usize,defValue: String)->String{
-        match self {
+        match self.loaded() {
             DxValue::Object(T) =>{
-                let vec = T.borrow();
+                let vec = T;
                 if index < vec.len(){
                     if let Some(v) =  vec.get(index){
-                        return v.Value.borrow().as_String();
+                        return v.Value.as_String();
                     }
                 }
             },
             DxValue::Array(T)=>{
-                let arr = T.borrow();
+                let arr = T;
                 if index < arr.len(){
                     if let Some(v) = arr.get(index){
                         return v.as_String();
@@ -664,41 +1044,30 @@ impl DxValue {
         defValue
     }
 
+    /// 与int_byName一致，超出isize范围时返回defValue
     pub fn int_byIndex(&self,index: usize,defValue: isize)->isize{
-        match self {
-            DxValue::Object(T) =>{
-                let vec = T.borrow();
-                if index < vec.len(){
-                    if let Some(v) =  vec.get(index){
-                        return v.Value.borrow().as_int();
-                    }
-                }
-            },
-            DxValue::Array(T)=>{
-                let arr = T.borrow();
-                if index < arr.len(){
-                    if let Some(v) = arr.get(index){
-                        return v.as_int();
-                    }
-                }
-            },
-            _=>(),
+        self.try_int_byIndex(index).unwrap_or(defValue)
+    }
+
+    pub fn try_int_byIndex(&self,index: usize) -> std::result::Result<isize,errValueReson>{
+        match self.get_byIndex(index) {
+            Some(v) => v.try_as_integer::<isize>(),
+            None => Err(errValueReson::VET_NotFound),
         }
-        defValue
     }
 
     pub fn bool_byIndex(&self,index: usize,defValue: bool)->bool{
-        match self {
+        match self.loaded() {
             DxValue::Object(t) =>{
-                let vec = t.borrow();
+                let vec = t;
                 if index < vec.len(){
                     if let Some(v) =  vec.get(index){
-                        return v.Value.borrow().as_bool();
+                        return v.Value.as_bool();
                     }
                 }
             },
             DxValue::Array(t)=>{
-                let arr = t.borrow();
+                let arr = t;
                 if index < arr.len(){
                     if let Some(v) = arr.get(index){
                         return v.as_bool();
@@ -711,17 +1080,17 @@ impl DxValue {
     }
 
     pub fn float_byIndex(&self,index: usize,defValue: f32)->f32{
-        match self {
+        match self.loaded() {
             DxValue::Object(t) =>{
-                let vec = t.borrow();
+                let vec = t;
                 if index < vec.len(){
                     if let Some(v) =  vec.get(index){
-                        return v.Value.borrow().as_float();
+                        return v.Value.as_float();
                     }
                 }
             },
             DxValue::Array(t)=>{
-                let arr = t.borrow();
+                let arr = t;
                 if index < arr.len(){
                     if let Some(v) = arr.get(index){
                         return v.as_float();
@@ -734,17 +1103,17 @@ impl DxValue {
     }
 
     pub fn double_byIndex(&self,index: usize,defValue: f64)->f64{
-        match self {
+        match self.loaded() {
             DxValue::Object(t) =>{
-                let vec = t.borrow();
+                let vec = t;
                 if index < vec.len(){
                     if let Some(v) =  vec.get(index){
-                        return v.Value.borrow().as_double();
+                        return v.Value.as_double();
                     }
                 }
             },
             DxValue::Array(t)=>{
-                let arr = t.borrow();
+                let arr = t;
                 if index < arr.len(){
                     if let Some(v) = arr.get(index){
                         return v.as_double();
@@ -755,4 +1124,38 @@ impl DxValue {
         }
         defValue
     }
+
+    pub fn binary_byIndex(&self,index: usize)->Vec<u8>{
+        match self.loaded() {
+            DxValue::Object(t) =>{
+                if let Some(v) = t.get(index){
+                    return v.Value.as_binary();
+                }
+            },
+            DxValue::Array(t)=>{
+                if let Some(v) = t.get(index){
+                    return v.as_binary();
+                }
+            },
+            _=>(),
+        }
+        Vec::new()
+    }
+
+    pub fn datetime_byIndex(&self,index: usize)->Option<DxDateTime>{
+        match self.loaded() {
+            DxValue::Object(t) =>{
+                if let Some(v) = t.get(index){
+                    return v.Value.as_datetime();
+                }
+            },
+            DxValue::Array(t)=>{
+                if let Some(v) = t.get(index){
+                    return v.as_datetime();
+                }
+            },
+            _=>(),
+        }
+        None
+    }
 }
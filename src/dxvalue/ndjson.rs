@@ -0,0 +1,138 @@
+//! NDJSON(JSON Lines)：每行一个JSON值。
+//! 读取时逐行解析，某一行出错不影响后面的行，除非设置了stop_on_error。
+//! 设置了max_total_bytes时它也是单行的长度上限，超长的行不会整行读入内存
+
+use std::io::{self, BufRead, Read, Write};
+use crate::dxvalue::DxValue;
+use crate::dxvalue::json::{self, errJsonReson, errorJson, ParseOptions};
+
+pub struct NdjsonReader<R: BufRead>{
+    reader: R,
+    opts: ParseOptions,
+    line: usize,
+    offset: usize,      //已读取的字节数
+    stop_on_error: bool,
+    stopped: bool,
+    buf: Vec<u8>,
+}
+
+impl<R: BufRead> NdjsonReader<R>{
+    pub fn new(reader: R) -> Self{
+        NdjsonReader::with_options(reader,ParseOptions::default())
+    }
+
+    pub fn with_options(reader: R,opts: ParseOptions) -> Self{
+        NdjsonReader{reader,opts,line: 0,offset: 0,stop_on_error: false,stopped: false,buf: Vec::new()}
+    }
+
+    /// 为true时第一个错误之后不再继续读取
+    pub fn stop_on_error(mut self,stop: bool) -> Self{
+        self.stop_on_error = stop;
+        self
+    }
+
+    /// 已经读取的行数
+    pub fn line(&self) -> usize{
+        self.line
+    }
+
+    /// 丢弃当前行剩下的部分
+    fn skipLine(&mut self) -> io::Result<()>{
+        loop {
+            let chunk = self.reader.fill_buf()?;
+            if chunk.is_empty(){
+                return Ok(());
+            }
+            match chunk.iter().position(|&c| c == b'\n') {
+                Some(n) => {
+                    self.reader.consume(n + 1);
+                    self.offset += n + 1;
+                    return Ok(());
+                },
+                None => {
+                    let n = chunk.len();
+                    self.reader.consume(n);
+                    self.offset += n;
+                },
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for NdjsonReader<R>{
+    /// 每个非空行一个结果，错误中带有行号，位置是在整个流中的字节位置
+    type Item = json::Result;
+
+    fn next(&mut self) -> Option<json::Result>{
+        if self.stopped{
+            return None;
+        }
+        loop {
+            self.buf.clear();
+            let start = self.offset;
+            //有max_total_bytes时每行最多读入这么多字节，超长的行不会整行放进内存
+            let cap = self.opts.max_total_bytes;
+            let read = if cap > 0 {
+                (&mut self.reader).take(cap as u64 + 1).read_until(b'\n',&mut self.buf)
+            } else {
+                self.reader.read_until(b'\n',&mut self.buf)
+            };
+            match read {
+                Ok(0) => return None,
+                Ok(n) => self.offset += n,
+                Err(_) => {
+                    //读取失败后流的状态不确定，总是停止
+                    self.stopped = true;
+                    return Some(Err(errorJson::new(start as isize,errJsonReson::JET_UnParse).with_line(self.line + 1)));
+                },
+            }
+            self.line += 1;
+            if cap > 0 && self.buf.len() > cap && self.buf.last() != Some(&b'\n'){
+                let err = errorJson::new((start + cap) as isize,errJsonReson::JET_InputTooLarge).with_line(self.line);
+                if self.stop_on_error || self.skipLine().is_err(){
+                    self.stopped = true;
+                }
+                self.buf.clear();
+                return Some(Err(err));
+            }
+            while let Some(b'\n') | Some(b'\r') = self.buf.last(){
+                self.buf.pop();
+            }
+            //跳过空行
+            if self.buf.iter().all(|c| c.is_ascii_whitespace()){
+                continue;
+            }
+            let line = self.line;
+            let result = json::parseJsonWithOptions(&self.buf,&self.opts).map_err(|e| e.offset(start).with_line(line));
+            if result.is_err() && self.stop_on_error{
+                self.stopped = true;
+            }
+            return Some(result);
+        }
+    }
+}
+
+/// 把DxValue逐个写成一行
+pub struct NdjsonWriter<W: Write>{
+    writer: W,
+}
+
+impl<W: Write> NdjsonWriter<W>{
+    pub fn new(writer: W) -> Self{
+        NdjsonWriter{writer}
+    }
+
+    /// 按json::toJson输出，浮点数不丢精度；含有NaN/Infinity时返回InvalidData，不写入任何内容
+    pub fn write(&mut self,value: &DxValue) -> io::Result<()>{
+        let line = json::toJson(value).map_err(|e| io::Error::new(io::ErrorKind::InvalidData,format!("{:?}",e)))?;
+        writeln!(self.writer,"{}",line)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()>{
+        self.writer.flush()
+    }
+
+    pub fn into_inner(self) -> W{
+        self.writer
+    }
+}
@@ -0,0 +1,179 @@
+//! 多线程解析大输入：NDJSON按行、顶层数组按元素切分后分给多个线程解析，
+//! 结果保持输入中的顺序，错误位置是在整个输入中的字节位置
+
+use std::sync::Arc;
+use std::thread;
+use crate::dxvalue::DxValue;
+use crate::dxvalue::encoding::{self, InputEncoding};
+use crate::dxvalue::json::{self, ParseOptions};
+
+fn threadCount(threads: usize) -> usize{
+    if threads > 0{
+        return threads;
+    }
+    thread::available_parallelism().map_or(1,|n| n.get())
+}
+
+/// 把[0,len)切成最多n段，每段的结束位置由cut决定(向后对齐到记录边界)
+fn chunks(len: usize,n: usize,cut: impl Fn(usize) -> usize) -> Vec<(usize,usize)>{
+    let mut out = Vec::new();
+    let mut start = 0;
+    for i in 1..=n{
+        if start >= len{
+            break;
+        }
+        let end = if i == n { len } else { cut((len * i / n).max(start)).min(len) };
+        if end > start{
+            out.push((start,end));
+            start = end;
+        }
+    }
+    out
+}
+
+/// 并行解析NDJSON，每个非空行一个结果，错误带有全局行号和字节位置。
+/// threads为0时使用CPU核数
+pub fn parseNdjsonParallel(buf: &[u8],opts: &ParseOptions,threads: usize) -> Vec<json::Result>{
+    let parts = chunks(buf.len(),threadCount(threads),|pos| {
+        buf[pos..].iter().position(|&c| c == b'\n').map_or(buf.len(),|n| pos + n + 1)
+    });
+    //每段返回(行数,结果)，行号在合并时加上前面各段的行数
+    let outputs: Vec<(usize,Vec<json::Result>)> = thread::scope(|s| {
+        let handles: Vec<_> = parts.iter().map(|&(start,end)| s.spawn(move || {
+            let mut results = Vec::new();
+            let mut lines = 0;
+            let mut pos = start;
+            while pos < end{
+                let lineEnd = buf[pos..end].iter().position(|&c| c == b'\n').map_or(end,|n| pos + n);
+                lines += 1;
+                let mut line = &buf[pos..lineEnd];
+                while let Some(b'\r') = line.last(){
+                    line = &line[..line.len() - 1];
+                }
+                if !line.iter().all(|c| c.is_ascii_whitespace()){
+                    let lineNo = lines;
+                    results.push(json::parseJsonWithOptions(line,opts).map_err(|e| e.offset(pos).with_line(lineNo)));
+                }
+                pos = lineEnd + 1;
+            }
+            (lines,results)
+        })).collect();
+        handles.into_iter().map(|h| h.join().unwrap_or_else(|e| std::panic::resume_unwind(e))).collect()
+    });
+    let mut all = Vec::new();
+    let mut base = 0;
+    for (lines,results) in outputs{
+        all.extend(results.into_iter().map(|r| r.map_err(|e| {
+            let line = e.line() + base;
+            e.with_line(line)
+        })));
+        base += lines;
+    }
+    all
+}
+
+/// 找出顶层数组各元素的字节范围；不是数组或者结构不完整时返回None
+fn splitArray(buf: &[u8]) -> Option<Vec<(usize,usize)>>{
+    let mut i = if buf.starts_with(&[0xEF,0xBB,0xBF]) { 3 } else { 0 };
+    while i < buf.len() && buf[i].is_ascii_whitespace(){
+        i += 1;
+    }
+    if buf.get(i) != Some(&b'['){
+        return None;
+    }
+    i += 1;
+    let mut items = Vec::new();
+    let mut itemStart = i;
+    let mut depth = 0usize;
+    let mut inString = false;
+    while i < buf.len(){
+        let c = buf[i];
+        if inString{
+            match c {
+                b'\\' => i += 1,
+                b'"' => inString = false,
+                _ => {},
+            }
+        }else{
+            match c {
+                b'"' => inString = true,
+                b'[' | b'{' => depth += 1,
+                b']' | b'}' if depth > 0 => depth -= 1,
+                b',' if depth == 0 => {
+                    items.push((itemStart,i));
+                    itemStart = i + 1;
+                },
+                b']' => {
+                    //数组结束，后面只能是空白
+                    if !buf[i + 1..].iter().all(|c| c.is_ascii_whitespace()){
+                        return None;
+                    }
+                    if !items.is_empty() || !buf[itemStart..i].iter().all(|c| c.is_ascii_whitespace()){
+                        items.push((itemStart,i));
+                    }
+                    //空元素(如末尾逗号)交给单线程解析报错或按选项接受
+                    if items.iter().any(|&(s,e)| buf[s..e].iter().all(|c| c.is_ascii_whitespace())){
+                        return None;
+                    }
+                    return Some(items);
+                },
+                b'}' => return None,
+                _ => {},
+            }
+        }
+        i += 1;
+    }
+    None
+}
+
+/// 并行解析顶层为数组的大文档，得到的值和parseJsonWithOptions相同。
+/// 只有UTF-8输入按元素切分并行解析；其它编码、开启注释或JSON5(元素边界无法简单确定)、
+/// lazy_depth为1时退回单线程解析。任何一个元素出错时整个输入再单线程解析一遍，
+/// 因此错误的原因和位置也和parseJsonWithOptions相同
+pub fn parseArrayParallel(buf: &[u8],opts: &ParseOptions,threads: usize) -> json::Result{
+    //UTF-16/32、GBK要先整体转码，按原始字节切分会切断编码单元
+    let utf8 = match opts.encoding {
+        InputEncoding::Auto => encoding::detect(buf).0 == InputEncoding::Utf8,
+        enc => enc == InputEncoding::Utf8,
+    };
+    let ranges = match splitArray(buf) {
+        Some(r) if utf8 && !opts.allow_comments && !opts.json5 && opts.max_depth != 1 && opts.lazy_depth != 1
+            && (opts.max_container_len == 0 || r.len() <= opts.max_container_len) => r,
+        //超出限制时也交给单线程解析，报告准确的错误
+        _ => return json::parseJsonWithOptions(buf,opts),
+    };
+    if opts.max_total_bytes > 0 && buf.len() > opts.max_total_bytes{
+        return json::parseJsonWithOptions(buf,opts);
+    }
+    //元素在外层数组之内，嵌套层数少一层；元素已确定是UTF-8，不再单独识别编码
+    let mut elemOpts = opts.clone();
+    elemOpts.max_depth = opts.max_depth.saturating_sub(1);
+    elemOpts.lazy_depth = opts.lazy_depth.saturating_sub(1);
+    elemOpts.encoding = InputEncoding::Utf8;
+    let full = opts;
+    let opts = &elemOpts;
+    let parts = chunks(ranges.len(),threadCount(threads),|i| i);
+    let outputs: Vec<json::Result> = thread::scope(|s| {
+        let handles: Vec<_> = parts.iter().map(|&(from,to)| {
+            let ranges = &ranges[from..to];
+            s.spawn(move || {
+                let mut items = Vec::with_capacity(ranges.len());
+                for &(start,end) in ranges{
+                    items.push(json::parseJsonWithOptions(&buf[start..end],opts)?);
+                }
+                Ok(DxValue::Array(Arc::new(items)))
+            })
+        }).collect();
+        handles.into_iter().map(|h| h.join().unwrap_or_else(|e| std::panic::resume_unwind(e))).collect()
+    });
+    let mut items = Vec::with_capacity(ranges.len());
+    for part in outputs{
+        match part {
+            Ok(DxValue::Array(arr)) => items.extend(Arc::try_unwrap(arr).unwrap_or_else(|a| (*a).clone())),
+            Ok(_) => {},
+            //单独解析一个元素时的错误原因可能不同，如 [1 2] 被当成一个元素
+            Err(_) => return json::parseJsonWithOptions(buf,full),
+        }
+    }
+    Ok(DxValue::Array(Arc::new(items)))
+}
@@ -0,0 +1,47 @@
+//! 延迟解析的JSON片段：语法已经校验过，但还没有构造成Object/Array，
+//! 输出时原样写回。只读访问时解析一次并缓存(clone之间共享)，节点本身不变；
+//! 通过value_byName/value_byIndex等可变访问进入时展开成Object/Array
+
+use std::fmt::{Display, Formatter, Result};
+use std::sync::{Arc, OnceLock};
+use crate::dxvalue::DxValue;
+use crate::dxvalue::json::{self, errorJson, ParseOptions};
+
+#[derive(Debug,Clone)]
+pub struct DxRawJson{
+    text: Arc<str>,
+    opts: Arc<ParseOptions>,    //展开时使用的解析选项
+    parsed: Arc<OnceLock<Option<DxValue>>>,   //只读访问时解析的结果，解析失败为None
+}
+
+impl DxRawJson{
+    pub(crate) fn from_parts(text: &str,opts: Arc<ParseOptions>) -> Self{
+        DxRawJson{text: Arc::from(text),opts,parsed: Arc::new(OnceLock::new())}
+    }
+
+    /// 校验text是合法的JSON后保存，不构造值
+    pub fn new(text: &str) -> std::result::Result<Self,errorJson>{
+        json::validateJson(text.as_bytes(),&ParseOptions::default())?;
+        Ok(DxRawJson::from_parts(text,Arc::new(ParseOptions::default())))
+    }
+
+    pub fn as_str(&self) -> &str{
+        &self.text
+    }
+
+    /// 解析成DxValue，lazy_depth选项仍然有效，更深的子节点继续保持延迟
+    pub fn parse(&self) -> json::Result{
+        json::parseJsonWithOptions(self.text.as_bytes(),&self.opts)
+    }
+
+    /// 解析后的值，第一次调用时解析并缓存；解析失败(如超出解析选项的限制)时为None
+    pub fn value(&self) -> Option<&DxValue>{
+        self.parsed.get_or_init(|| self.parse().ok()).as_ref()
+    }
+}
+
+impl Display for DxRawJson{
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        f.write_str(&self.text)
+    }
+}
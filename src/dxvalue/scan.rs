@@ -0,0 +1,275 @@
+//! 解析器的热点扫描：跳过空白、查找字符串中的引号/反斜杠/控制字符、UTF-8校验。
+//! x86_64上使用SSE2(基线指令集)，运行时检测到AVX2时改用AVX2，其它平台逐字节处理。
+//! UTF-8校验只有AVX2版本(查表法需要pshufb)，没有AVX2时使用标准库
+
+#[inline]
+fn isWhite(c: u8) -> bool{
+    matches!(c,b' ' | b'\n' | b'\t' | b'\r')
+}
+
+/// 开头连续空白(空格、\n、\t、\r)的字节数
+#[inline]
+pub fn skipWhitespace(b: &[u8]) -> usize{
+    //大多数情况下下一个字符就不是空白
+    match b.first() {
+        Some(&c) if isWhite(c) => {},
+        _ => return 0,
+    }
+    #[cfg(target_arch = "x86_64")]
+    {
+        if b.len() >= 16{
+            return x86::skipWhitespace(b);
+        }
+    }
+    scalar::skipWhitespace(b)
+}
+
+/// 字符串内第一个引号(quote)、反斜杠或控制字符的位置，没有时返回b.len()
+#[inline]
+pub fn findStringSpecial(b: &[u8],quote: u8) -> usize{
+    #[cfg(target_arch = "x86_64")]
+    {
+        if b.len() >= 16{
+            return x86::findStringSpecial(b,quote);
+        }
+    }
+    scalar::findStringSpecial(b,quote)
+}
+
+/// 校验UTF-8，失败时返回合法部分的长度。
+/// 向量版本只判断是否合法，出错时再由标准库找出准确的位置
+pub fn validateUtf8(b: &[u8]) -> Result<&str,usize>{
+    #[cfg(target_arch = "x86_64")]
+    {
+        if b.len() >= 32 && x86::hasAvx2() && unsafe { x86::validateUtf8Avx2(b) }{
+            return Ok(unsafe { std::str::from_utf8_unchecked(b) });
+        }
+    }
+    std::str::from_utf8(b).map_err(|e| e.valid_up_to())
+}
+
+pub(crate) mod scalar{
+    pub fn skipWhitespace(b: &[u8]) -> usize{
+        b.iter().position(|&c| !super::isWhite(c)).unwrap_or(b.len())
+    }
+
+    pub fn findStringSpecial(b: &[u8],quote: u8) -> usize{
+        b.iter().position(|&c| c == quote || c == b'\\' || c < 0x20).unwrap_or(b.len())
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+pub(crate) mod x86{
+    use std::arch::x86_64::*;
+
+    //各函数先处理整块，剩余不足一块的部分交给逐字节实现
+
+    pub fn skipWhitespace(b: &[u8]) -> usize{
+        if is_x86_feature_detected!("avx2"){
+            unsafe { skipWhitespaceAvx2(b) }
+        }else{
+            unsafe { skipWhitespaceSse2(b) }
+        }
+    }
+
+    pub fn findStringSpecial(b: &[u8],quote: u8) -> usize{
+        if is_x86_feature_detected!("avx2"){
+            unsafe { findStringSpecialAvx2(b,quote) }
+        }else{
+            unsafe { findStringSpecialSse2(b,quote) }
+        }
+    }
+
+    pub fn hasAvx2() -> bool{
+        is_x86_feature_detected!("avx2")
+    }
+
+    #[target_feature(enable = "sse2")]
+    pub unsafe fn skipWhitespaceSse2(b: &[u8]) -> usize{
+        let (sp,lf,tab,cr) = (_mm_set1_epi8(b' ' as i8),_mm_set1_epi8(b'\n' as i8),_mm_set1_epi8(b'\t' as i8),_mm_set1_epi8(b'\r' as i8));
+        let mut i = 0;
+        while i + 16 <= b.len(){
+            let v = _mm_loadu_si128(b.as_ptr().add(i) as *const __m128i);
+            let white = _mm_or_si128(_mm_or_si128(_mm_cmpeq_epi8(v,sp),_mm_cmpeq_epi8(v,lf)),
+                _mm_or_si128(_mm_cmpeq_epi8(v,tab),_mm_cmpeq_epi8(v,cr)));
+            let mask = _mm_movemask_epi8(white) as u32;
+            if mask != 0xFFFF{
+                return i + mask.trailing_ones() as usize;
+            }
+            i += 16;
+        }
+        i + super::scalar::skipWhitespace(&b[i..])
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn skipWhitespaceAvx2(b: &[u8]) -> usize{
+        let (sp,lf,tab,cr) = (_mm256_set1_epi8(b' ' as i8),_mm256_set1_epi8(b'\n' as i8),_mm256_set1_epi8(b'\t' as i8),_mm256_set1_epi8(b'\r' as i8));
+        let mut i = 0;
+        while i + 32 <= b.len(){
+            let v = _mm256_loadu_si256(b.as_ptr().add(i) as *const __m256i);
+            let white = _mm256_or_si256(_mm256_or_si256(_mm256_cmpeq_epi8(v,sp),_mm256_cmpeq_epi8(v,lf)),
+                _mm256_or_si256(_mm256_cmpeq_epi8(v,tab),_mm256_cmpeq_epi8(v,cr)));
+            let mask = _mm256_movemask_epi8(white) as u32;
+            if mask != u32::MAX{
+                return i + mask.trailing_ones() as usize;
+            }
+            i += 32;
+        }
+        i + skipWhitespaceSse2(&b[i..])
+    }
+
+    #[target_feature(enable = "sse2")]
+    pub unsafe fn findStringSpecialSse2(b: &[u8],quote: u8) -> usize{
+        let (q,bs,ctrl) = (_mm_set1_epi8(quote as i8),_mm_set1_epi8(b'\\' as i8),_mm_set1_epi8(0x1F));
+        let mut i = 0;
+        while i + 16 <= b.len(){
+            let v = _mm_loadu_si128(b.as_ptr().add(i) as *const __m128i);
+            //无符号比较 v <= 0x1F 等价于 max(v,0x1F) == 0x1F
+            let low = _mm_cmpeq_epi8(_mm_max_epu8(v,ctrl),ctrl);
+            let hit = _mm_or_si128(_mm_or_si128(_mm_cmpeq_epi8(v,q),_mm_cmpeq_epi8(v,bs)),low);
+            let mask = _mm_movemask_epi8(hit) as u32;
+            if mask != 0{
+                return i + mask.trailing_zeros() as usize;
+            }
+            i += 16;
+        }
+        i + super::scalar::findStringSpecial(&b[i..],quote)
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn findStringSpecialAvx2(b: &[u8],quote: u8) -> usize{
+        let (q,bs,ctrl) = (_mm256_set1_epi8(quote as i8),_mm256_set1_epi8(b'\\' as i8),_mm256_set1_epi8(0x1F));
+        let mut i = 0;
+        while i + 32 <= b.len(){
+            let v = _mm256_loadu_si256(b.as_ptr().add(i) as *const __m256i);
+            let low = _mm256_cmpeq_epi8(_mm256_max_epu8(v,ctrl),ctrl);
+            let hit = _mm256_or_si256(_mm256_or_si256(_mm256_cmpeq_epi8(v,q),_mm256_cmpeq_epi8(v,bs)),low);
+            let mask = _mm256_movemask_epi8(hit) as u32;
+            if mask != 0{
+                return i + mask.trailing_zeros() as usize;
+            }
+            i += 32;
+        }
+        i + findStringSpecialSse2(&b[i..],quote)
+    }
+
+    //UTF-8校验采用Keiser & Lemire的查表法(simdjson)：每个字节和它前面1~3个字节的高低半字节
+    //各查一张表，三张表的结果按位与之后非0就是某一类错误；3、4字节序列的后续字节单独检查
+    const TOO_SHORT: u8 = 1 << 0;       //首字节后面跟着ASCII或另一个首字节
+    const TOO_LONG: u8 = 1 << 1;        //ASCII后面跟着后续字节
+    const OVERLONG_3: u8 = 1 << 2;
+    const TOO_LARGE: u8 = 1 << 3;       //超过U+10FFFF
+    const SURROGATE: u8 = 1 << 4;       //U+D800..U+DFFF
+    const OVERLONG_2: u8 = 1 << 5;
+    const TOO_LARGE_1000: u8 = 1 << 6;
+    const OVERLONG_4: u8 = 1 << 6;
+    const TWO_CONTS: u8 = 1 << 7;       //连续两个后续字节(3、4字节序列中的合法情况由must23抵消)
+    const CARRY: u8 = TOO_SHORT | TOO_LONG | TWO_CONTS;
+
+    //前一个字节的高半字节
+    const BYTE_1_HIGH: [u8;16] = [
+        TOO_LONG,TOO_LONG,TOO_LONG,TOO_LONG,TOO_LONG,TOO_LONG,TOO_LONG,TOO_LONG,
+        TWO_CONTS,TWO_CONTS,TWO_CONTS,TWO_CONTS,
+        TOO_SHORT | OVERLONG_2,
+        TOO_SHORT,
+        TOO_SHORT | OVERLONG_3 | SURROGATE,
+        TOO_SHORT | TOO_LARGE | TOO_LARGE_1000 | OVERLONG_4,
+    ];
+    //前一个字节的低半字节
+    const BYTE_1_LOW: [u8;16] = [
+        CARRY | OVERLONG_3 | OVERLONG_2 | OVERLONG_4,
+        CARRY | OVERLONG_2,
+        CARRY,
+        CARRY,
+        CARRY | TOO_LARGE,
+        CARRY | TOO_LARGE | TOO_LARGE_1000,
+        CARRY | TOO_LARGE | TOO_LARGE_1000,
+        CARRY | TOO_LARGE | TOO_LARGE_1000,
+        CARRY | TOO_LARGE | TOO_LARGE_1000,
+        CARRY | TOO_LARGE | TOO_LARGE_1000,
+        CARRY | TOO_LARGE | TOO_LARGE_1000,
+        CARRY | TOO_LARGE | TOO_LARGE_1000,
+        CARRY | TOO_LARGE | TOO_LARGE_1000,
+        CARRY | TOO_LARGE | TOO_LARGE_1000 | SURROGATE,
+        CARRY | TOO_LARGE | TOO_LARGE_1000,
+        CARRY | TOO_LARGE | TOO_LARGE_1000,
+    ];
+    //当前字节的高半字节
+    const BYTE_2_HIGH: [u8;16] = [
+        TOO_SHORT,TOO_SHORT,TOO_SHORT,TOO_SHORT,TOO_SHORT,TOO_SHORT,TOO_SHORT,TOO_SHORT,
+        TOO_LONG | OVERLONG_2 | TWO_CONTS | OVERLONG_3 | TOO_LARGE_1000 | OVERLONG_4,
+        TOO_LONG | OVERLONG_2 | TWO_CONTS | OVERLONG_3 | TOO_LARGE,
+        TOO_LONG | OVERLONG_2 | TWO_CONTS | SURROGATE | TOO_LARGE,
+        TOO_LONG | OVERLONG_2 | TWO_CONTS | SURROGATE | TOO_LARGE,
+        TOO_SHORT,TOO_SHORT,TOO_SHORT,TOO_SHORT,
+    ];
+    //块末尾的最后3个字节不小于这些值时，多字节序列延续到了下一块
+    const INCOMPLETE: [u8;32] = {
+        let mut t = [0xFF;32];
+        t[29] = 0xF0 - 1;
+        t[30] = 0xE0 - 1;
+        t[31] = 0xC0 - 1;
+        t
+    };
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn lookup16(table: &[u8;16],idx: __m256i) -> __m256i{
+        let t = _mm256_broadcastsi128_si256(_mm_loadu_si128(table.as_ptr() as *const __m128i));
+        _mm256_shuffle_epi8(t,idx)
+    }
+
+    /// 每个字节前面第1、2、3个字节，跨块时取上一块末尾的字节
+    #[target_feature(enable = "avx2")]
+    unsafe fn prevBytes(input: __m256i,prev: __m256i) -> (__m256i,__m256i,__m256i){
+        let joined = _mm256_permute2x128_si256(prev,input,0x21);
+        (_mm256_alignr_epi8(input,joined,15),_mm256_alignr_epi8(input,joined,14),_mm256_alignr_epi8(input,joined,13))
+    }
+
+    /// 一块中的错误，结果非0表示不合法
+    #[target_feature(enable = "avx2")]
+    unsafe fn blockErrors(input: __m256i,prev: __m256i) -> __m256i{
+        let nibble = _mm256_set1_epi8(0x0F);
+        let (prev1,prev2,prev3) = prevBytes(input,prev);
+        let special = _mm256_and_si256(
+            _mm256_and_si256(
+                lookup16(&BYTE_1_HIGH,_mm256_and_si256(_mm256_srli_epi16(prev1,4),nibble)),
+                lookup16(&BYTE_1_LOW,_mm256_and_si256(prev1,nibble))),
+            lookup16(&BYTE_2_HIGH,_mm256_and_si256(_mm256_srli_epi16(input,4),nibble)));
+        //前面第2个字节是3、4字节序列的首字节，或第3个字节是4字节序列的首字节时，当前字节必须是后续字节
+        let third = _mm256_subs_epu8(prev2,_mm256_set1_epi8((0xE0u8 - 0x80) as i8));
+        let fourth = _mm256_subs_epu8(prev3,_mm256_set1_epi8((0xF0u8 - 0x80) as i8));
+        let must23 = _mm256_and_si256(_mm256_or_si256(third,fourth),_mm256_set1_epi8(0x80u8 as i8));
+        _mm256_xor_si256(must23,special)
+    }
+
+    /// 整个输入是否为合法的UTF-8
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn validateUtf8Avx2(b: &[u8]) -> bool{
+        let incompleteMax = _mm256_loadu_si256(INCOMPLETE.as_ptr() as *const __m256i);
+        let mut error = _mm256_setzero_si256();
+        let mut prev = _mm256_setzero_si256();
+        let mut prevIncomplete = _mm256_setzero_si256();
+        let mut block = |input: __m256i| {
+            if _mm256_movemask_epi8(input) == 0{
+                //整块都是ASCII，只要上一块没有以不完整的序列结尾
+                error = _mm256_or_si256(error,prevIncomplete);
+                prevIncomplete = _mm256_setzero_si256();
+            }else{
+                error = _mm256_or_si256(error,blockErrors(input,prev));
+                prevIncomplete = _mm256_subs_epu8(input,incompleteMax);
+            }
+            prev = input;
+        };
+        let mut i = 0;
+        while i + 32 <= b.len(){
+            block(_mm256_loadu_si256(b.as_ptr().add(i) as *const __m256i));
+            i += 32;
+        }
+        //剩余部分补0(ASCII)凑成一块，同时检查了结尾处不完整的序列
+        let mut tail = [0u8;32];
+        tail[..b.len() - i].copy_from_slice(&b[i..]);
+        block(_mm256_loadu_si256(tail.as_ptr() as *const __m256i));
+        error = _mm256_or_si256(error,prevIncomplete);
+        _mm256_testz_si256(error,error) == 1
+    }
+}
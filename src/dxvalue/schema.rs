@@ -0,0 +1,676 @@
+//! JSON Schema(draft 2020-12)校验。schema本身是DxValue，编译时预先编译正则、收集$id/$anchor并检查$ref，
+//! 校验时返回所有错误，每个错误带实例中的位置和schema中的位置(都是JSON Pointer)。
+//! 只解析文档内的引用(#/..、#anchor、文档中出现过的$id)，不支持unevaluated*和远程引用
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::net::{Ipv4Addr, Ipv6Addr};
+use regex::Regex;
+use crate::dxvalue::{base64, escapePointerToken, DxDateTime, DxValue, Key_Value};
+
+/// 正在校验中的(被引用的schema位置,实例位置)，同一对再次出现说明$ref循环且没有深入实例
+type ActiveRefs = HashSet<(String,String)>;
+
+/// 直接作用于当前实例、不含子schema的关键字，按此顺序报告错误
+const KEYWORDS: [&str; 19] = ["type","enum","const","minimum","maximum","exclusiveMinimum","exclusiveMaximum","multipleOf",
+    "minLength","maxLength","pattern","format","required","dependentRequired","minProperties","maxProperties",
+    "minItems","maxItems","uniqueItems"];
+
+/// 值为单个子schema的关键字
+const SCHEMA_KEYWORDS: [&str; 8] = ["items","contains","additionalProperties","propertyNames","not","if","then","else"];
+
+/// 值为子schema数组的关键字
+const SCHEMA_ARRAY_KEYWORDS: [&str; 4] = ["allOf","anyOf","oneOf","prefixItems"];
+
+/// 值为 名字->子schema 的关键字，其中的名字是属性名，不是关键字
+const SCHEMA_MAP_KEYWORDS: [&str; 5] = ["properties","patternProperties","dependentSchemas","$defs","definitions"];
+
+#[derive(Debug,Clone,PartialEq)]
+pub struct ValidationError{
+    /// 出错的实例位置
+    pub instance_path: String,
+    /// 出错的关键字在schema中的位置，经过$ref时是被引用处的位置
+    pub schema_path: String,
+    pub keyword: &'static str,
+    pub message: String,
+}
+
+impl Display for ValidationError{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f,"{}: {} (schema {})",if self.instance_path.is_empty() { "/" } else { &self.instance_path },self.message,self.schema_path)
+    }
+}
+
+pub struct Validator{
+    root: DxValue,
+    regexes: HashMap<String,Regex>,
+    ids: HashMap<String,String>,     //$id(完整URI)、URI#anchor到schema中JSON Pointer的映射
+    refs: HashMap<String,String>,    //$ref关键字所在位置到被引用的JSON Pointer
+    formats: bool,
+}
+
+fn members(v: &DxValue) -> &[Key_Value]{
+    match v {
+        DxValue::Object(obj) => obj,
+        _ => &[],
+    }
+}
+
+fn childPath(parent: &str,token: &str) -> String{
+    format!("{}/{}",parent,escapePointerToken(token))
+}
+
+/// 把Raw子树全部展开，schema编译后按JSON Pointer查找
+fn expandAll(v: &mut DxValue){
+    v.expandRaw();
+    match v {
+        DxValue::Object(obj) => std::sync::Arc::make_mut(obj).iter_mut().for_each(|kv| expandAll(&mut kv.Value)),
+        DxValue::Array(arr) => std::sync::Arc::make_mut(arr).iter_mut().for_each(expandAll),
+        _ => {},
+    }
+}
+
+/// 相对base解析URI引用(RFC 3986 5.2，不处理.和..)
+fn joinUri(base: &str,r: &str) -> String{
+    let hasScheme = r.find(':').is_some_and(|i| !r[..i].contains('/'));
+    if r.is_empty() || base.is_empty() || hasScheme{
+        return if r.is_empty() { base.to_string() } else { r.to_string() };
+    }
+    if r.starts_with('/'){
+        //保留scheme和authority
+        let rootEnd = match base.find("://") {
+            Some(i) => base[i + 3..].find('/').map_or(base.len(),|j| i + 3 + j),
+            None => 0,
+        };
+        return format!("{}{}",&base[..rootEnd],r);
+    }
+    match base.rfind('/') {
+        Some(i) => format!("{}{}",&base[..=i],r),
+        None => r.to_string(),
+    }
+}
+
+fn percentDecode(s: &str) -> String{
+    let b = s.as_bytes();
+    let mut out = Vec::with_capacity(b.len());
+    let mut i = 0;
+    while i < b.len(){
+        if b[i] == b'%' && i + 2 < b.len(){
+            if let Ok(c) = u8::from_str_radix(&s[i + 1..i + 3],16){
+                out.push(c);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(b[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+impl Validator{
+    pub fn new(schema: &DxValue) -> Result<Self,ValidationError>{
+        let mut root = schema.clone();
+        expandAll(&mut root);
+        let mut v = Validator{root: DxValue::None,regexes: HashMap::new(),ids: HashMap::new(),refs: HashMap::new(),formats: true};
+        let mut refs = Vec::new();
+        v.compile(&root,"","",&mut refs)?;
+        for (r,at,base) in refs{
+            match v.resolve(&r,&base) {
+                Some(ptr) if root.get_byPointer(&ptr).is_some() => {
+                    v.refs.insert(at,ptr);
+                },
+                _ => return Err(ValidationError{
+                    instance_path: String::new(),
+                    schema_path: at,
+                    keyword: "$ref",
+                    message: format!("无法解析的引用 {}",r),
+                }),
+            }
+        }
+        v.root = root;
+        Ok(v)
+    }
+
+    /// 是否校验format，默认校验
+    pub fn validate_formats(mut self,on: bool) -> Self{
+        self.formats = on;
+        self
+    }
+
+    /// 校验实例，返回所有错误
+    pub fn validate(&self,instance: &DxValue) -> Result<(),Vec<ValidationError>>{
+        let mut errs = Vec::new();
+        self.check(&self.root,"",instance,"",&mut ActiveRefs::new(),&mut errs);
+        if errs.is_empty() { Ok(()) } else { Err(errs) }
+    }
+
+    pub fn is_valid(&self,instance: &DxValue) -> bool{
+        let mut errs = Vec::new();
+        self.check(&self.root,"",instance,"",&mut ActiveRefs::new(),&mut errs);
+        errs.is_empty()
+    }
+
+    /// 按关键字进入子schema；enum、const、default、examples以及未知关键字的值是数据，不进入。
+    /// base是当前schema所在资源的URI，嵌入的$id开始一个新的资源，其中的$ref相对它解析
+    fn compile(&mut self,schema: &DxValue,path: &str,base: &str,refs: &mut Vec<(String,String,String)>) -> Result<(),ValidationError>{
+        let compileErr = |path: String,keyword,message| ValidationError{instance_path: String::new(),schema_path: path,keyword,message};
+        let base = match schema.get_byName("$id") {
+            Some(DxValue::String(id)) => {
+                let uri = joinUri(base,id.trim_end_matches('#'));
+                self.ids.insert(uri.clone(),path.to_string());
+                uri
+            },
+            _ => base.to_string(),
+        };
+        let base = base.as_str();
+        for kv in members(schema){
+            let at = childPath(path,&kv.Key);
+            match (kv.Key.as_str(),&kv.Value) {
+                ("$anchor",DxValue::String(name)) => {
+                    self.ids.insert(format!("{}#{}",base,name),path.to_string());
+                },
+                ("$ref",DxValue::String(r)) => refs.push((r.clone(),at,base.to_string())),
+                ("pattern",DxValue::String(p)) => self.compileRegex(p).map_err(|e| compileErr(at,"pattern",e))?,
+                (k,DxValue::Object(props)) if SCHEMA_MAP_KEYWORDS.contains(&k) => {
+                    for p in props.iter(){
+                        let sub = childPath(&at,&p.Key);
+                        if k == "patternProperties"{
+                            self.compileRegex(&p.Key).map_err(|e| compileErr(sub.clone(),"patternProperties",e))?;
+                        }
+                        self.compile(&p.Value,&sub,base,refs)?;
+                    }
+                },
+                (k,DxValue::Array(arr)) if SCHEMA_ARRAY_KEYWORDS.contains(&k) => {
+                    for (i,v) in arr.iter().enumerate(){
+                        self.compile(v,&format!("{}/{}",at,i),base,refs)?;
+                    }
+                },
+                (k,v) if SCHEMA_KEYWORDS.contains(&k) => self.compile(v,&at,base,refs)?,
+                _ => {},
+            }
+        }
+        Ok(())
+    }
+
+    fn compileRegex(&mut self,pattern: &str) -> Result<(),String>{
+        if !self.regexes.contains_key(pattern){
+            let re = Regex::new(pattern).map_err(|e| format!("非法的正则表达式: {}",e))?;
+            self.regexes.insert(pattern.to_string(),re);
+        }
+        Ok(())
+    }
+
+    /// 把$ref相对base换算为schema中的JSON Pointer
+    fn resolve(&self,r: &str,base: &str) -> Option<String>{
+        let (uri,frag) = r.split_once('#').unwrap_or((r,""));
+        let resource = joinUri(base,uri);
+        //没有$id的根文档URI为空
+        let prefix = if resource.is_empty() { "" } else { self.ids.get(&resource)? };
+        if frag.is_empty(){
+            Some(prefix.to_string())
+        }else if frag.starts_with('/'){
+            Some(format!("{}{}",prefix,percentDecode(frag)))
+        }else{
+            self.ids.get(&format!("{}#{}",resource,frag)).cloned()
+        }
+    }
+
+    fn check(&self,schema: &DxValue,spath: &str,inst: &DxValue,ipath: &str,active: &mut ActiveRefs,errs: &mut Vec<ValidationError>){
+        if let DxValue::Raw(raw) = inst{
+            match raw.value() {
+                Some(v) => self.check(schema,spath,v,ipath,active,errs),
+                None => errs.push(ValidationError{instance_path: ipath.to_string(),schema_path: spath.to_string(),keyword: "type",message: "无法解析的JSON片段".to_string()}),
+            }
+            return;
+        }
+        match schema {
+            DxValue::Boolean(false) => {
+                errs.push(ValidationError{instance_path: ipath.to_string(),schema_path: spath.to_string(),keyword: "false",message: "schema为false，不允许任何值".to_string()});
+                return;
+            },
+            DxValue::Object(_) => {},
+            _ => return,
+        }
+        let mut fail = |keyword: &'static str,message: String| errs.push(ValidationError{
+            instance_path: ipath.to_string(),
+            schema_path: childPath(spath,keyword),
+            keyword,
+            message,
+        });
+        for &name in KEYWORDS.iter(){
+            let kw = match schema.get_byName(name) {
+                Some(kw) => kw,
+                None => continue,
+            };
+            match name {
+                "type" => {
+                    let ok = match kw {
+                        DxValue::String(t) => typeMatches(inst,t),
+                        DxValue::Array(ts) => ts.iter().any(|t| matches!(t,DxValue::String(t) if typeMatches(inst,t))),
+                        _ => true,
+                    };
+                    if !ok{
+                        fail("type",format!("类型应为{}，实际为{}",kw,typeName(inst)));
+                    }
+                },
+                "enum" => {
+                    if let DxValue::Array(values) = kw{
+                        if !values.iter().any(|v| jsonEqual(v,inst)){
+                            fail("enum",format!("值不在{}中",kw));
+                        }
+                    }
+                },
+                "const" if !jsonEqual(kw,inst) => fail("const",format!("值应为{}",kw)),
+                "minimum" | "maximum" | "exclusiveMinimum" | "exclusiveMaximum" => {
+                    if let (Some(v),Some(limit)) = (numberOf(inst),numberOf(kw)){
+                        let ok = match name {
+                            "minimum" => v >= limit,
+                            "maximum" => v <= limit,
+                            "exclusiveMinimum" => v > limit,
+                            _ => v < limit,
+                        };
+                        if !ok{
+                            fail(name,format!("{}超出{} {}",inst,name,kw));
+                        }
+                    }
+                },
+                "multipleOf" if numberOf(inst).is_some() && !isMultiple(inst,kw) => {
+                    fail("multipleOf",format!("{}不是{}的倍数",inst,kw));
+                },
+                "minLength" | "maxLength" => {
+                    if let (Some(s),Some(limit)) = (stringOf(inst),countOf(kw)){
+                        let n = s.chars().count();
+                        if name == "minLength" && n < limit{
+                            fail("minLength",format!("长度{}小于{}",n,limit));
+                        }else if name == "maxLength" && n > limit{
+                            fail("maxLength",format!("长度{}大于{}",n,limit));
+                        }
+                    }
+                },
+                "pattern" => {
+                    if let (Some(s),DxValue::String(p)) = (stringOf(inst),kw){
+                        match self.regexes.get(p.as_str()) {
+                            Some(re) if re.is_match(&s) => {},
+                            Some(_) => fail("pattern",format!("不匹配{}",p)),
+                            //$ref指向了编译时没有进入的位置
+                            None => fail("pattern",format!("正则表达式{}未编译",p)),
+                        }
+                    }
+                },
+                "format" => {
+                    if let (true,Some(s),DxValue::String(f)) = (self.formats,stringOf(inst),kw){
+                        if checkFormat(f,&s) == Some(false){
+                            fail("format",format!("不是合法的{}",f));
+                        }
+                    }
+                },
+                "required" => {
+                    if let (DxValue::Object(_),DxValue::Array(names)) = (inst,kw){
+                        for name in names.iter(){
+                            if let DxValue::String(name) = name{
+                                if inst.get_byName(name).is_none(){
+                                    fail("required",format!("缺少属性{}",name));
+                                }
+                            }
+                        }
+                    }
+                },
+                "dependentRequired" => {
+                    if let DxValue::Object(_) = inst{
+                        for dep in members(kw){
+                            if inst.get_byName(&dep.Key).is_none(){
+                                continue;
+                            }
+                            if let DxValue::Array(names) = &dep.Value{
+                                for name in names.iter(){
+                                    if let DxValue::String(name) = name{
+                                        if inst.get_byName(name).is_none(){
+                                            fail("dependentRequired",format!("有{}时必须有{}",dep.Key,name));
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                },
+                "minProperties" | "maxProperties" => {
+                    if let (DxValue::Object(m),Some(limit)) = (inst,countOf(kw)){
+                        if name == "minProperties" && m.len() < limit{
+                            fail("minProperties",format!("属性个数{}小于{}",m.len(),limit));
+                        }else if name == "maxProperties" && m.len() > limit{
+                            fail("maxProperties",format!("属性个数{}大于{}",m.len(),limit));
+                        }
+                    }
+                },
+                "minItems" | "maxItems" => {
+                    if let (DxValue::Array(a),Some(limit)) = (inst,countOf(kw)){
+                        if name == "minItems" && a.len() < limit{
+                            fail("minItems",format!("元素个数{}小于{}",a.len(),limit));
+                        }else if name == "maxItems" && a.len() > limit{
+                            fail("maxItems",format!("元素个数{}大于{}",a.len(),limit));
+                        }
+                    }
+                },
+                "uniqueItems" => {
+                    if let (DxValue::Array(a),DxValue::Boolean(true)) = (inst,kw){
+                        if let Some(i) = (1..a.len()).find(|&i| a[..i].iter().any(|v| jsonEqual(v,&a[i]))){
+                            fail("uniqueItems",format!("第{}个元素重复",i));
+                        }
+                    }
+                },
+                _ => {},
+            }
+        }
+        //以下关键字的子schema错误直接加入errs
+        let sub = |name: &str| schema.get_byName(name);
+        let at = |name: &str| childPath(spath,name);
+        let single = |errs: &mut Vec<ValidationError>,keyword: &'static str,message: String| errs.push(ValidationError{
+            instance_path: ipath.to_string(),
+            schema_path: childPath(spath,keyword),
+            keyword,
+            message,
+        });
+        if let Some(DxValue::String(r)) = sub("$ref"){
+            match self.refs.get(&at("$ref")).and_then(|ptr| Some((ptr,self.root.get_byPointer(ptr)?))) {
+                Some((ptr,target)) => {
+                    let key = (ptr.clone(),ipath.to_string());
+                    if active.insert(key.clone()){
+                        self.check(target,ptr,inst,ipath,active,errs);
+                        active.remove(&key);
+                    }else{
+                        single(errs,"$ref",format!("循环引用{}",r));
+                    }
+                },
+                None => single(errs,"$ref",format!("引用{}未编译",r)),
+            }
+        }
+        if let Some(DxValue::Array(all)) = sub("allOf"){
+            for (i,s) in all.iter().enumerate(){
+                self.check(s,&format!("{}/{}",at("allOf"),i),inst,ipath,active,errs);
+            }
+        }
+        if let Some(DxValue::Array(any)) = sub("anyOf"){
+            if !any.iter().enumerate().any(|(i,s)| self.passes(s,&format!("{}/{}",at("anyOf"),i),inst,ipath,active)){
+                single(errs,"anyOf","不满足anyOf中的任何一个".to_string());
+            }
+        }
+        if let Some(DxValue::Array(one)) = sub("oneOf"){
+            let n = one.iter().enumerate().filter(|(i,s)| self.passes(s,&format!("{}/{}",at("oneOf"),i),inst,ipath,active)).count();
+            if n != 1{
+                single(errs,"oneOf",format!("应恰好满足oneOf中的一个，实际满足{}个",n));
+            }
+        }
+        if let Some(not) = sub("not"){
+            if self.passes(not,&at("not"),inst,ipath,active){
+                single(errs,"not","不应满足not".to_string());
+            }
+        }
+        if let Some(cond) = sub("if"){
+            let (branch,name) = if self.passes(cond,&at("if"),inst,ipath,active) { (sub("then"),"then") } else { (sub("else"),"else") };
+            if let Some(branch) = branch{
+                self.check(branch,&at(name),inst,ipath,active,errs);
+            }
+        }
+        match inst {
+            DxValue::Object(_) => self.checkObject(schema,spath,inst,ipath,active,errs),
+            DxValue::Array(a) => self.checkArray(schema,spath,a,ipath,active,errs),
+            _ => {},
+        }
+    }
+
+    fn passes(&self,schema: &DxValue,spath: &str,inst: &DxValue,ipath: &str,active: &mut ActiveRefs) -> bool{
+        let mut errs = Vec::new();
+        self.check(schema,spath,inst,ipath,active,&mut errs);
+        errs.is_empty()
+    }
+
+    fn checkObject(&self,schema: &DxValue,spath: &str,inst: &DxValue,ipath: &str,active: &mut ActiveRefs,errs: &mut Vec<ValidationError>){
+        let m = members(inst);
+        let props = schema.get_byName("properties");
+        let patterns = members(schema.get_byName("patternProperties").unwrap_or(&DxValue::None));
+        let additional = schema.get_byName("additionalProperties");
+        let names = schema.get_byName("propertyNames");
+        let deps = members(schema.get_byName("dependentSchemas").unwrap_or(&DxValue::None));
+        for kv in m{
+            let path = childPath(ipath,&kv.Key);
+            let mut matched = false;
+            if let Some(s) = props.and_then(|p| p.get_byName(&kv.Key)){
+                matched = true;
+                self.check(s,&childPath(&childPath(spath,"properties"),&kv.Key),&kv.Value,&path,active,errs);
+            }
+            for p in patterns{
+                let ppath = childPath(&childPath(spath,"patternProperties"),&p.Key);
+                match self.regexes.get(p.Key.as_str()) {
+                    Some(re) if re.is_match(&kv.Key) => {
+                        matched = true;
+                        self.check(&p.Value,&ppath,&kv.Value,&path,active,errs);
+                    },
+                    Some(_) => {},
+                    None => errs.push(ValidationError{
+                        instance_path: path.clone(),
+                        schema_path: ppath,
+                        keyword: "patternProperties",
+                        message: format!("正则表达式{}未编译",p.Key),
+                    }),
+                }
+            }
+            if let (false,Some(s)) = (matched,additional){
+                self.check(s,&childPath(spath,"additionalProperties"),&kv.Value,&path,active,errs);
+            }
+            if let Some(s) = names{
+                self.check(s,&childPath(spath,"propertyNames"),&DxValue::String(kv.Key.clone()),&path,active,errs);
+            }
+        }
+        for dep in deps{
+            if m.iter().any(|kv| kv.Key == dep.Key){
+                self.check(&dep.Value,&childPath(&childPath(spath,"dependentSchemas"),&dep.Key),inst,ipath,active,errs);
+            }
+        }
+    }
+
+    fn checkArray(&self,schema: &DxValue,spath: &str,a: &[DxValue],ipath: &str,active: &mut ActiveRefs,errs: &mut Vec<ValidationError>){
+        let mut prefix = 0;
+        if let Some(DxValue::Array(items)) = schema.get_byName("prefixItems"){
+            prefix = items.len();
+            for (i,(s,v)) in items.iter().zip(a).enumerate(){
+                self.check(s,&format!("{}/prefixItems/{}",spath,i),v,&format!("{}/{}",ipath,i),active,errs);
+            }
+        }
+        if let Some(s) = schema.get_byName("items"){
+            for (i,v) in a.iter().enumerate().skip(prefix){
+                self.check(s,&childPath(spath,"items"),v,&format!("{}/{}",ipath,i),active,errs);
+            }
+        }
+        if let Some(s) = schema.get_byName("contains"){
+            let spath = childPath(spath,"contains");
+            let n = a.iter().enumerate().filter(|(i,v)| self.passes(s,&spath,v,&format!("{}/{}",ipath,i),active)).count();
+            let min = schema.get_byName("minContains").and_then(countOf).unwrap_or(1);
+            let max = schema.get_byName("maxContains").and_then(countOf);
+            if n < min || max.is_some_and(|max| n > max){
+                errs.push(ValidationError{
+                    instance_path: ipath.to_string(),
+                    schema_path: spath,
+                    keyword: "contains",
+                    message: format!("满足contains的元素有{}个",n),
+                });
+            }
+        }
+    }
+}
+
+pub(crate) fn typeName(v: &DxValue) -> &'static str{
+    match v {
+        DxValue::None => "null",
+        DxValue::Boolean(_) => "boolean",
+        DxValue::Object(_) => "object",
+        DxValue::Array(_) => "array",
+        //解析失败时按文本的第一个字符判断
+        DxValue::Raw(raw) => match raw.value() {
+            Some(v) => typeName(v),
+            None => match raw.as_str().trim_start().bytes().next() {
+                Some(b'{') => "object",
+                Some(b'[') => "array",
+                Some(b'"') | Some(b'\'') => "string",
+                Some(b't') | Some(b'f') => "boolean",
+                Some(b'n') => "null",
+                _ => "number",
+            },
+        },
+        DxValue::String(_) | DxValue::Binary(_) | DxValue::DateTime(_) => "string",
+        _ if isInteger(v) => "integer",
+        _ => "number",
+    }
+}
+
+fn typeMatches(v: &DxValue,name: &str) -> bool{
+    match name {
+        "number" => numberOf(v).is_some(),
+        "integer" => isInteger(v),
+        _ => typeName(v) == name,
+    }
+}
+
+pub(crate) fn numberOf(v: &DxValue) -> Option<f64>{
+    match v {
+        DxValue::Int(_) | DxValue::Int32(_) | DxValue::Int64(_) | DxValue::UInt64(_) | DxValue::Int128(_) | DxValue::UInt128(_)
+        | DxValue::Float(_) | DxValue::Double(_) | DxValue::Decimal(_) | DxValue::RawNumber(_) => Some(v.as_double()),
+        _ => None,
+    }
+}
+
+/// 整数类型按精确值取出，用于multipleOf
+pub(crate) fn exactInteger(v: &DxValue) -> Option<i128>{
+    match v {
+        DxValue::Int(_) | DxValue::Int32(_) | DxValue::Int64(_) | DxValue::UInt64(_) | DxValue::Int128(_) | DxValue::UInt128(_) => v.try_as_integer().ok(),
+        _ => None,
+    }
+}
+
+/// 小数部分为0的数字也是integer
+fn isInteger(v: &DxValue) -> bool{
+    exactInteger(v).is_some() || numberOf(v).is_some_and(|f| f.is_finite() && f.fract() == 0.0)
+}
+
+fn isMultiple(v: &DxValue,m: &DxValue) -> bool{
+    if let (Some(a),Some(b)) = (exactInteger(v),exactInteger(m)){
+        return b != 0 && a % b == 0;
+    }
+    match (numberOf(v),numberOf(m)) {
+        (Some(a),Some(b)) if b != 0.0 => {
+            let q = a / b;
+            (q - q.round()).abs() <= 1e-9 * q.abs().max(1.0)
+        },
+        _ => true,
+    }
+}
+
+fn countOf(v: &DxValue) -> Option<usize>{
+    numberOf(v).filter(|f| *f >= 0.0).map(|f| f as usize)
+}
+
+/// Binary/DateTime输出为字符串，也按字符串校验
+fn stringOf(v: &DxValue) -> Option<Cow<'_,str>>{
+    match v {
+        DxValue::String(s) => Some(Cow::Borrowed(s)),
+        DxValue::Binary(b) => Some(Cow::Owned(base64::encode(b))),
+        DxValue::DateTime(t) => Some(Cow::Owned(t.to_string())),
+        _ => None,
+    }
+}
+
+/// 按JSON语义比较：对象忽略键的顺序，1和1.0相等。
+/// 数字按精确值比较，大整数和Decimal不经过f64；NaN等于NaN
+pub(crate) fn jsonEqual(a: &DxValue,b: &DxValue) -> bool{
+    match (a,b) {
+        (DxValue::Raw(raw),_) => raw.value().is_some_and(|a| jsonEqual(a,b)),
+        (_,DxValue::Raw(raw)) => raw.value().is_some_and(|b| jsonEqual(a,b)),
+        (DxValue::None,DxValue::None) => true,
+        (DxValue::Boolean(x),DxValue::Boolean(y)) => x == y,
+        (DxValue::Array(x),DxValue::Array(y)) => x.len() == y.len() && x.iter().zip(y.iter()).all(|(a,b)| jsonEqual(a,b)),
+        (DxValue::Object(x),DxValue::Object(y)) => {
+            //重复键只看第一个，与get_byName一致
+            let keys: HashSet<&str> = x.iter().map(|kv| kv.Key.as_str()).collect();
+            keys.len() == y.iter().map(|kv| kv.Key.as_str()).collect::<HashSet<_>>().len()
+                && keys.iter().all(|k| matches!((a.get_byName(k),b.get_byName(k)),(Some(a),Some(b)) if jsonEqual(a,b)))
+        },
+        _ => match (numberOf(a),numberOf(b),stringOf(a),stringOf(b)) {
+            (Some(x),Some(y),_,_) => match (exactNumber(a),exactNumber(b)) {
+                (Some(a),Some(b)) => a == b,
+                //NaN/Infinity
+                (None,None) => x == y || (x.is_nan() && y.is_nan()),
+                _ => false,
+            },
+            (_,_,Some(x),Some(y)) => x == y,
+            _ => false,
+        },
+    }
+}
+
+/// 数字的精确值：(是否为负，去掉首尾0的有效数字，最后一位数字的指数)，0为(false,"",0)。
+/// 浮点数取最短往返的十进制表示，NaN/Infinity返回None
+pub(crate) fn exactNumber(v: &DxValue) -> Option<(bool,String,i64)>{
+    let text = match v {
+        DxValue::UInt128(t) => t.to_string(),
+        DxValue::Decimal(d) => format!("{}e{}",d.coeff(),d.exp()),
+        DxValue::Float(t) if t.is_finite() => format!("{:e}",t),
+        DxValue::Double(t) if t.is_finite() => format!("{:e}",t),
+        DxValue::RawNumber(t) => t.to_string(),
+        _ => exactInteger(v)?.to_string(),
+    };
+    let (mantissa,exp) = match text.find(['e','E']) {
+        Some(i) => (&text[..i],text[i + 1..].parse::<i64>().ok()?),
+        None => (text.as_str(),0),
+    };
+    let (negative,mantissa) = match mantissa.strip_prefix('-') {
+        Some(m) => (true,m),
+        None => (false,mantissa),
+    };
+    let (int,frac) = mantissa.split_once('.').unwrap_or((mantissa,""));
+    let digits = format!("{}{}",int,frac);
+    if digits.is_empty() || !digits.bytes().all(|c| c.is_ascii_digit()){
+        return None;
+    }
+    let digits = digits.trim_start_matches('0');
+    let trimmed = digits.trim_end_matches('0');
+    if trimmed.is_empty(){
+        return Some((false,String::new(),0));
+    }
+    let exp = exp - frac.len() as i64 + (digits.len() - trimmed.len()) as i64;
+    Some((negative,trimmed.to_string(),exp))
+}
+
+/// 不认识的format返回None
+fn checkFormat(format: &str,s: &str) -> Option<bool>{
+    Some(match format {
+        "date-time" => DxDateTime::parse_rfc3339(s).is_some(),
+        "date" => s.len() == 10 && DxDateTime::parse_rfc3339(&format!("{}T00:00:00Z",s)).is_some(),
+        "time" => DxDateTime::parse_rfc3339(&format!("2000-01-01T{}",s)).is_some(),
+        "email" => match s.rsplit_once('@') {
+            Some((local,domain)) => !local.is_empty() && !s.contains(char::is_whitespace) && isHostname(domain),
+            None => false,
+        },
+        "hostname" => isHostname(s),
+        "ipv4" => s.parse::<Ipv4Addr>().is_ok(),
+        "ipv6" => s.parse::<Ipv6Addr>().is_ok(),
+        "uri" => match s.split_once(':') {
+            Some((scheme,_)) => scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c,'+' | '-' | '.'))
+                && !s.contains(|c: char| c.is_whitespace() || c.is_control()),
+            None => false,
+        },
+        "uuid" => s.len() == 36 && s.char_indices().all(|(i,c)| if matches!(i,8 | 13 | 18 | 23) { c == '-' } else { c.is_ascii_hexdigit() }),
+        "regex" => Regex::new(s).is_ok(),
+        "json-pointer" => (s.is_empty() || s.starts_with('/')) && s.split('~').skip(1).all(|t| t.starts_with(['0','1'])),
+        _ => return None,
+    })
+}
+
+fn isHostname(s: &str) -> bool{
+    let s = s.strip_suffix('.').unwrap_or(s);
+    !s.is_empty() && s.len() <= 253 && s.split('.').all(|label| {
+        !label.is_empty() && label.len() <= 63 && !label.starts_with('-') && !label.ends_with('-')
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    })
+}
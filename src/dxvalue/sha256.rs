@@ -0,0 +1,32 @@
+//! SHA-256，用于规范化JSON的内容哈希；计算由sha2 crate完成，这里只保留本库使用的接口
+
+use sha2::Digest;
+
+/// 可以分多次写入的SHA-256
+#[derive(Clone,Default)]
+pub struct Sha256{
+    inner: sha2::Sha256,
+}
+
+impl Sha256{
+    pub fn new() -> Self{
+        Sha256{inner: sha2::Sha256::new()}
+    }
+
+    pub fn update(&mut self,data: &[u8]){
+        self.inner.update(data);
+    }
+
+    pub fn finish(self) -> [u8; 32]{
+        self.inner.finalize().into()
+    }
+}
+
+pub fn digest(data: &[u8]) -> [u8; 32]{
+    sha2::Sha256::digest(data).into()
+}
+
+/// 小写十六进制
+pub fn toHex(hash: &[u8]) -> String{
+    hash.iter().map(|b| format!("{:02x}",b)).collect()
+}
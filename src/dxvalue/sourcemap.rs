@@ -0,0 +1,114 @@
+//! 解析时记录的节点位置表，按JSON Pointer查找每个值在原文中的起止位置(字节/行/列)，
+//! 用于在校验或取值失败时提示 "config.json line 42 column 7"
+
+use std::collections::HashMap;
+use crate::dxvalue::json::NodeSpan;
+
+/// 原文中的位置，line和column从1开始，column按字符计
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct SourcePos{
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug,Clone)]
+pub struct SourceSpan{
+    /// 节点的JSON Pointer
+    pub path: String,
+    /// 对象成员的键(含引号)的起始位置
+    pub key: Option<SourcePos>,
+    pub start: SourcePos,
+    /// 值结束后的位置
+    pub end: SourcePos,
+}
+
+#[derive(Debug,Clone,Default)]
+pub struct SourceMap{
+    file: Option<String>,
+    spans: Vec<SourceSpan>,
+    index: HashMap<String,usize>,
+}
+
+/// 计算offset处的行列，需要从头扫描，适合偶尔定位解析错误
+pub fn lineColumn(text: &[u8],offset: usize) -> SourcePos{
+    let offset = offset.min(text.len());
+    let mut pos = SourcePos{offset,line: 1,column: 1};
+    for &c in &text[..offset]{
+        if c == b'\n'{
+            pos.line += 1;
+            pos.column = 1;
+        }else if c & 0xC0 != 0x80{
+            pos.column += 1;
+        }
+    }
+    pos
+}
+
+impl SourceMap{
+    /// 对所有需要的位置排序后扫描一遍原文计算行列
+    pub(crate) fn build(text: &[u8],nodes: Vec<NodeSpan>) -> Self{
+        let mut offsets: Vec<usize> = nodes.iter()
+            .flat_map(|n| n.key_start.into_iter().chain([n.start,n.end]))
+            .collect();
+        offsets.sort_unstable();
+        offsets.dedup();
+        let mut positions: HashMap<usize,SourcePos> = HashMap::with_capacity(offsets.len());
+        let (mut line,mut column,mut i) = (1,1,0);
+        for offset in offsets{
+            while i < offset.min(text.len()){
+                if text[i] == b'\n'{
+                    line += 1;
+                    column = 1;
+                }else if text[i] & 0xC0 != 0x80{
+                    column += 1;
+                }
+                i += 1;
+            }
+            positions.insert(offset,SourcePos{offset,line,column});
+        }
+        let mut spans: Vec<SourceSpan> = nodes.into_iter().map(|n| SourceSpan{
+            key: n.key_start.map(|k| positions[&k]),
+            start: positions[&n.start],
+            end: positions[&n.end],
+            path: n.path,
+        }).collect();
+        //节点是在值解析完之后记录的，按原文顺序排列
+        spans.sort_by_key(|s| s.start.offset);
+        let mut index = HashMap::with_capacity(spans.len());
+        for (i,s) in spans.iter().enumerate(){
+            //重复的键只记第一个，与get_byName一致
+            index.entry(s.path.clone()).or_insert(i);
+        }
+        SourceMap{file: None,spans,index}
+    }
+
+    pub fn with_file(mut self,file: &str) -> Self{
+        self.file = Some(file.to_string());
+        self
+    }
+
+    pub fn file(&self) -> Option<&str>{
+        self.file.as_deref()
+    }
+
+    /// 按JSON Pointer查找节点，空串为根节点
+    pub fn get(&self,path: &str) -> Option<&SourceSpan>{
+        self.index.get(path).map(|&i| &self.spans[i])
+    }
+
+    /// 所有节点，按在原文中的先后顺序
+    pub fn spans(&self) -> &[SourceSpan]{
+        &self.spans
+    }
+
+    /// 如 "config.json line 42 column 7"，对象成员指向键的位置
+    pub fn describe(&self,path: &str) -> Option<String>{
+        let span = self.get(path)?;
+        let pos = span.key.unwrap_or(span.start);
+        Some(match &self.file {
+            Some(file) => format!("{} line {} column {}",file,pos.line,pos.column),
+            None => format!("line {} column {}",pos.line,pos.column),
+        })
+    }
+}
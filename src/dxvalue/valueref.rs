@@ -0,0 +1,127 @@
+//! 借用输入缓冲区的只读值：没有转义的字符串和键直接引用输入，
+//! 数字保留原文，需要修改或长期保存时用to_value转换成DxValue
+
+use std::borrow::Cow;
+use std::sync::Arc;
+use crate::dxvalue::json::ParseOptions;
+use crate::dxvalue::{base64, DxDateTime, DxDecimal, DxValue, Key_Value, numberFromText};
+
+#[derive(Debug,Clone,PartialEq)]
+pub enum DxValueRef<'a>{
+    None,
+    Boolean(bool),
+    Number(Cow<'a,str>),        //数字原文，JSON5的写法会规范成标准JSON数字
+    String(Cow<'a,str>),
+    Array(Vec<DxValueRef<'a>>),
+    Object(Vec<(Cow<'a,str>,DxValueRef<'a>)>),
+}
+
+//Infinity/NaN只能来自JSON5，解析时总是Double
+fn numberValue(text: &str,opts: &ParseOptions) -> DxValue{
+    let nonFinite = text.ends_with("Infinity") || text == "NaN";
+    if opts.keep_number_text && !nonFinite{
+        return DxValue::RawNumber(text.to_string());
+    }
+    if opts.decimal && !nonFinite && text.contains(['.','e','E']){
+        if let Ok(v) = text.parse::<DxDecimal>(){
+            return DxValue::Decimal(v);
+        }
+    }
+    numberFromText(text).unwrap_or_else(|| DxValue::RawNumber(text.to_string()))
+}
+
+impl From<&DxValueRef<'_>> for DxValue{
+    fn from(v: &DxValueRef<'_>) -> Self{
+        v.to_value()
+    }
+}
+
+impl<'a> DxValueRef<'a>{
+    /// 转换为普通的DxValue，数字按parseJson的规则选择类型
+    pub fn to_value(&self) -> DxValue{
+        self.to_value_with(&ParseOptions::default())
+    }
+
+    /// 按opts中决定值类型的选项(keep_number_text、decimal、parse_datetime、base64_keys)转换，
+    /// 结果与用同样的选项调用parseJsonWithOptions一致
+    pub fn to_value_with(&self,opts: &ParseOptions) -> DxValue{
+        match self {
+            DxValueRef::None => DxValue::None,
+            DxValueRef::Boolean(b) => DxValue::Boolean(*b),
+            DxValueRef::Number(text) => numberValue(text,opts),
+            DxValueRef::String(s) => {
+                if opts.parse_datetime{
+                    if let Some(dt) = DxDateTime::parse_rfc3339(s){
+                        return DxValue::DateTime(dt);
+                    }
+                }
+                DxValue::String(s.to_string())
+            },
+            DxValueRef::Array(arr) => DxValue::Array(Arc::new(arr.iter().map(|v| v.to_value_with(opts)).collect())),
+            DxValueRef::Object(obj) => {
+                DxValue::Object(Arc::new(obj.iter().map(|(k,v)| {
+                    let mut value = v.to_value_with(opts);
+                    if let DxValue::String(s) = &value{
+                        if opts.base64_keys.iter().any(|key| key == k){
+                            if let Some(bin) = base64::decode(s){
+                                value = DxValue::Binary(bin);
+                            }
+                        }
+                    }
+                    Key_Value::new_value(k,value)
+                }).collect()))
+            },
+        }
+    }
+
+    pub fn get_byName(&self,name: &str) -> Option<&DxValueRef<'a>>{
+        match self {
+            DxValueRef::Object(obj) => obj.iter().find(|(k,_)| k == name).map(|(_,v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn get_byIndex(&self,index: usize) -> Option<&DxValueRef<'a>>{
+        match self {
+            DxValueRef::Array(arr) => arr.get(index),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str>{
+        match self {
+            DxValueRef::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// 字符串是否直接引用输入(没有经过复制)
+    pub fn is_borrowed(&self) -> bool{
+        matches!(self,DxValueRef::String(Cow::Borrowed(_)))
+    }
+
+    pub fn as_i64(&self) -> Option<i64>{
+        match self {
+            DxValueRef::Number(text) => text.parse().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64>{
+        match self {
+            DxValueRef::Number(text) => text.parse().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool>{
+        match self {
+            DxValueRef::Boolean(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn is_null(&self) -> bool{
+        matches!(self,DxValueRef::None)
+    }
+}
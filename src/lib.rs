@@ -2,8 +2,1047 @@ pub mod dxvalue;
 
 #[cfg(test)]
 mod tests {
+    use crate::dxvalue::json::{self, errJsonReson, DuplicateKeys, ParseOptions};
+    use crate::dxvalue::jsondoc::JsonDocument;
+    use crate::dxvalue::ndjson::{NdjsonReader, NdjsonWriter};
+    use crate::dxvalue::parallel;
+    use crate::dxvalue::scan;
+    use crate::dxvalue::encoding::InputEncoding;
+    use crate::dxvalue::sourcemap;
+    use crate::dxvalue::{canonical, sha256};
+    use crate::dxvalue::schema::Validator;
+    use crate::dxvalue::infer::{self, SchemaInferrer};
+    use crate::dxvalue::codegen::{self, CodegenOptions, FromDxValue, ToDxValue};
+    use crate::dxvalue::diff::{self, ArrayMode, ChangeKind, DiffOptions};
+    use crate::dxvalue::merge::{self, Conflict, PreferTheirs, Resolution};
+    use crate::dxvalue::{base64, errValueReson, DxDateTime, DxDecimal, DxRawJson, DxValue, DxValueRef, Key_Value};
+    use std::sync::Arc;
+    use std::thread;
+
     #[test]
     fn it_works() {
         assert_eq!(2 + 2, 4);
     }
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn dxvalue_is_send_sync() {
+        assert_send_sync::<DxValue>();
+        assert_send_sync::<Key_Value>();
+    }
+
+    #[test]
+    fn share_across_threads() {
+        let mut cfg = DxValue::newObject();
+        cfg.setKeyString("name", "不得闲");
+        cfg.setKeyValue("port", DxValue::Int(8080));
+        let cfg = Arc::new(cfg);
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let cfg = Arc::clone(&cfg);
+                thread::spawn(move || (cfg.string_byName("name", ""), cfg.int_byName("port", 0)))
+            })
+            .collect();
+        for h in handles {
+            assert_eq!(h.join().unwrap(), ("\"不得闲\"".to_string(), 8080));
+        }
+    }
+
+    #[test]
+    fn clone_shares_subtree() {
+        let mut sub = DxValue::newObject();
+        sub.setKeyValue("level", DxValue::Int(1));
+        let mut cfg = DxValue::newObject();
+        cfg.setKeyValue("sub", sub);
+        let copy = cfg.clone();
+        match (&cfg, &copy) {
+            (DxValue::Object(a), DxValue::Object(b)) => assert!(Arc::ptr_eq(a, b)),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn copy_on_write_does_not_leak() {
+        let mut sub = DxValue::newObject();
+        sub.setKeyValue("level", DxValue::Int(1));
+        let mut cfg = DxValue::newObject();
+        cfg.setKeyValue("sub", sub);
+        cfg.setKeyValue("other", DxValue::newArray());
+        let mut holder = cfg.clone();
+
+        if let Some(sub) = cfg.value_byName("sub") {
+            sub.setKeyValue("level", DxValue::Int(2));
+        }
+        cfg.setKeyString("name", "edited");
+
+        assert_eq!(holder.to_string(), "{\"sub\": {\"level\": 1},\"other\": []}");
+        assert_eq!(
+            cfg.to_string(),
+            "{\"sub\": {\"level\": 2},\"other\": [],\"name\": \"edited\"}"
+        );
+        // 未修改的兄弟节点仍然共享
+        match (cfg.value_byIndex(1), holder.value_byIndex(1)) {
+            (Some(DxValue::Array(x)), Some(DxValue::Array(y))) => assert!(Arc::ptr_eq(x, y)),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn base64_round_trip() {
+        assert_eq!(base64::encode(b""), "");
+        assert_eq!(base64::encode(b"f"), "Zg==");
+        assert_eq!(base64::encode(b"foob"), "Zm9vYg==");
+        assert_eq!(base64::encode(b"foobar"), "Zm9vYmFy");
+        assert_eq!(base64::decode("Zm9vYg==").unwrap(), b"foob");
+        assert_eq!(base64::decode("Zm9vYg").unwrap(), b"foob");
+        assert!(base64::decode("Zm9v!g==").is_none());
+        let bytes: Vec<u8> = (0..=255).collect();
+        assert_eq!(base64::decode(&base64::encode(&bytes)).unwrap(), bytes);
+    }
+
+    #[test]
+    fn parse_json_document() {
+        let v = json::parseJson(
+            br#"{"name":"\u4e0d\u5f97\u95f2","n":[1,-2.5,true,null],"s":"a\"b\n"}"#.to_vec(),
+        )
+        .unwrap();
+        assert_eq!(v.string_byName("name", ""), "\"不得闲\"");
+        assert_eq!(v.to_string(), r#"{"name": "不得闲","n": [1,-2.50,true,null],"s": "a\"b\n"}"#);
+        let err = json::parseJson(br#"{"a":1"#.to_vec()).unwrap_err();
+        assert_eq!(err.reson(), errJsonReson::JET_NoObjBack);
+        let err = json::parseJson(br#"[1 2]"#.to_vec()).unwrap_err();
+        assert_eq!(err.reson(), errJsonReson::JET_NoValueSplit);
+        assert_eq!(err.pos(), 3);
+    }
+
+    #[test]
+    fn binary_value() {
+        let mut v = DxValue::newObject();
+        v.setKeyValue("blob", DxValue::Binary(vec![0xff, 0x00, 0x80]));
+        assert_eq!(v.to_string(), r#"{"blob": "/wCA"}"#);
+        assert_eq!(v.binary_byName("blob"), vec![0xff, 0x00, 0x80]);
+        if let Some(b) = v.value_byName("blob") {
+            b.setBinary(b"hi");
+        }
+        assert_eq!(v.binary_byIndex(0), b"hi");
+        let kv = Key_Value::new_binary("k", b"x");
+        assert_eq!(kv.to_string(), r#""k": "eA==""#);
+
+        let opts = ParseOptions { base64_keys: vec!["blob".to_string()], ..Default::default() };
+        let mut loaded = json::parseJsonWithOptions(br#"{"blob":"/wCA","name":"/wCA"}"#, &opts).unwrap();
+        assert!(matches!(loaded.value_byName("blob"), Some(DxValue::Binary(b)) if b == &[0xff, 0x00, 0x80]));
+        assert!(matches!(loaded.value_byName("name"), Some(DxValue::String(_))));
+    }
+
+    #[test]
+    fn datetime_rfc3339_and_epoch() {
+        let dt = DxDateTime::parse_rfc3339("2006-01-02T15:04:05.123+08:00").unwrap();
+        assert_eq!(dt.unix_millis(), 1136185445123);
+        assert_eq!(dt.offset_minutes(), 480);
+        assert_eq!(dt.to_rfc3339(), "2006-01-02T15:04:05.123+08:00");
+        assert_eq!(DxDateTime::from_unix_secs(0).to_rfc3339(), "1970-01-01T00:00:00Z");
+        assert_eq!(DxDateTime::from_unix_nanos(-1).to_rfc3339(), "1969-12-31T23:59:59.999999999Z");
+        assert_eq!(DxDateTime::from_unix_millis(dt.unix_millis()), DxDateTime::new(1136185445, 123_000_000));
+        assert!(DxDateTime::parse_rfc3339("2023-02-29T00:00:00Z").is_none());
+        assert!(DxDateTime::parse_rfc3339("2024-02-29T00:00:00").is_none());
+        let west = DxDateTime::from_unix_secs(0).with_offset(-(23 * 60 + 59)).unwrap();
+        assert_eq!(west.to_rfc3339(), "1969-12-31T00:01:00-23:59");
+        assert!(DxDateTime::from_unix_secs(0).with_offset(24 * 60).is_none());
+        assert!(DxDateTime::from_unix_secs(0).with_offset(i16::MIN).is_none());
+
+        let opts = ParseOptions { parse_datetime: true, ..Default::default() };
+        let v = json::parseJsonWithOptions(br#"{"at":"2024-02-29T12:00:00Z","s":"x"}"#, &opts).unwrap();
+        assert_eq!(v.datetime_byName("at").unwrap().unix_secs(), 1709208000);
+        assert!(v.datetime_byName("s").is_none());
+        assert_eq!(v.to_string(), r#"{"at": "2024-02-29T12:00:00Z","s": "x"}"#);
+        assert_eq!(DxValue::Int(1709208000).as_datetime(), v.datetime_byIndex(0));
+    }
+
+    #[test]
+    fn datetime_bson_msgpack() {
+        let dt = DxDateTime::from_unix_millis(-1500);
+        assert_eq!(DxDateTime::from_bson_datetime(dt.to_bson_datetime()), dt);
+        for t in [DxDateTime::from_unix_secs(1), DxDateTime::new(1, 5), DxDateTime::new(1 << 35, 7), dt].iter() {
+            let data = t.to_msgpack_timestamp();
+            assert_eq!(DxDateTime::from_msgpack_timestamp(&data), Some(*t));
+        }
+        assert_eq!(DxDateTime::from_unix_secs(1).to_msgpack_timestamp().len(), 4);
+        assert_eq!(DxDateTime::new(1, 5).to_msgpack_timestamp().len(), 8);
+        assert_eq!(dt.to_msgpack_timestamp().len(), 12);
+        //超出毫秒范围的秒数取极值，不溢出
+        let mut data = vec![0, 0, 0, 1];
+        data.extend_from_slice(&i64::MAX.to_be_bytes());
+        let far = DxDateTime::from_msgpack_timestamp(&data).unwrap();
+        assert_eq!((far.to_bson_datetime(), far.unix_millis()), (i64::MAX, i64::MAX));
+        assert_eq!(DxDateTime::from_unix_secs(i64::MIN).unix_millis(), i64::MIN);
+        assert!(!far.with_offset(60).unwrap().to_rfc3339().is_empty());
+    }
+
+    #[test]
+    fn wide_integers() {
+        let v = json::parseJson(
+            br#"[18446744073709551615,-170141183460469231731687303715884105728,340282366920938463463374607431768211455,1e3,340282366920938463463374607431768211456]"#
+                .to_vec(),
+        )
+        .unwrap();
+        assert!(matches!(v.get_byIndex(0), Some(DxValue::UInt64(u64::MAX))));
+        assert!(matches!(v.get_byIndex(1), Some(DxValue::Int128(i128::MIN))));
+        assert!(matches!(v.get_byIndex(2), Some(DxValue::UInt128(u128::MAX))));
+        assert!(matches!(v.get_byIndex(3), Some(DxValue::Double(_))));
+        assert!(matches!(v.get_byIndex(4), Some(DxValue::Double(_))));
+        assert_eq!(v.string_byIndex(0, String::new()), "18446744073709551615");
+
+        assert_eq!(v.try_int_byIndex(0), Err(errValueReson::VET_Overflow));
+        assert_eq!(v.int_byName("0", -1), -1);
+        assert_eq!(v.int_byIndex(0, -1), -1);
+        assert_eq!((v.get_byIndex(0).unwrap().as_int(), v.get_byIndex(1).unwrap().as_int()), (isize::MAX, isize::MIN));
+        assert_eq!(v.try_int_byIndex(9), Err(errValueReson::VET_NotFound));
+        assert_eq!(v.get_byIndex(0).unwrap().try_as_integer::<u64>(), Ok(u64::MAX));
+        assert_eq!(v.get_byIndex(3).unwrap().try_as_integer::<u64>(), Err(errValueReson::VET_TypeMismatch));
+        assert_eq!(DxValue::String("99999999999999999999".to_string()).try_as_integer::<i64>(), Err(errValueReson::VET_Overflow));
+        assert_eq!(DxValue::String("-".to_string()).try_as_integer::<i64>(), Err(errValueReson::VET_TypeMismatch));
+        assert_eq!(DxValue::UInt64(7).try_as_integer::<i8>(), Ok(7));
+    }
+
+    #[test]
+    fn decimal_values() {
+        let a: DxDecimal = "19.99".parse().unwrap();
+        let b: DxDecimal = "0.01".parse().unwrap();
+        assert_eq!(a.checked_add(&b).unwrap().to_string(), "20.00");
+        assert_eq!(a.checked_mul(&"3".parse().unwrap()).unwrap().to_string(), "59.97");
+        assert_eq!("1.005".parse::<DxDecimal>().unwrap().round_dp(2).unwrap().to_string(), "1.01");
+        assert_eq!("-1.005".parse::<DxDecimal>().unwrap().round_dp(2).unwrap().to_string(), "-1.01");
+        assert_eq!(DxDecimal::new(9 * 10i128.pow(37), -38).round_dp(0).unwrap().to_string(), "1");
+        assert_eq!(DxDecimal::new(-4 * 10i128.pow(37), -38).round_dp(0).unwrap().to_string(), "0");
+        assert_eq!(DxDecimal::new(5, -50).round_dp(2).unwrap().to_string(), "0.00");
+        assert_eq!("1.10".parse::<DxDecimal>().unwrap(), "1.1".parse::<DxDecimal>().unwrap());
+        assert!("1e400".parse::<DxDecimal>().unwrap() > "9".repeat(38).parse::<DxDecimal>().unwrap());
+        assert_eq!("-0.05".parse::<DxDecimal>().unwrap().to_string(), "-0.05");
+
+        let opts = ParseOptions { decimal: true, ..Default::default() };
+        let v = json::parseJsonWithOptions(br#"{"price":0.10,"qty":3,"big":1e400}"#, &opts).unwrap();
+        assert_eq!(v.to_string(), r#"{"price": 0.10,"qty": 3,"big": 1e400}"#);
+        assert_eq!(v.decimal_byName("price").unwrap().scale(), 2);
+        assert_eq!(v.double_byName("price", 0.0), 0.1);
+        assert_eq!(v.double_byName("big", 0.0), f64::INFINITY);
+        assert_eq!(v.get_byName("price").unwrap().try_as_integer::<i64>(), Err(errValueReson::VET_TypeMismatch));
+        assert_eq!(v.get_byName("big").unwrap().try_as_integer::<i64>(), Err(errValueReson::VET_Overflow));
+        let err = json::parseJsonWithOptions(format!("[0.{}]", "1".repeat(40)).as_bytes(), &opts).unwrap_err();
+        assert_eq!((err.reson(), err.pos()), (errJsonReson::JET_NumberRange, 1));
+    }
+
+    #[test]
+    fn decimal128_encoding() {
+        for text in ["0", "1.10", "-123456789012345678901234567890.1234", "1E+6111", "1E-6176"].iter() {
+            let d: DxDecimal = text.parse().unwrap();
+            let back = DxDecimal::from_decimal128_bytes(d.to_decimal128_bytes().unwrap()).unwrap();
+            assert_eq!((back.coeff(), back.exp()), (d.coeff(), d.exp()));
+        }
+        // BSON规范中的 1.0 = 0x3040...0A 的小端形式
+        let one: DxDecimal = "1.0".parse().unwrap();
+        let bytes = one.to_decimal128_bytes().unwrap();
+        assert_eq!(bytes[0], 0x0A);
+        assert_eq!(bytes[15], 0x30);
+        assert_eq!(bytes[14], 0x3E);
+        assert!("1e6200".parse::<DxDecimal>().unwrap().to_decimal128_bytes().is_none());
+        //35位系数末尾是0时去掉0后编码
+        let wide: DxDecimal = format!("1{}", "0".repeat(34)).parse().unwrap();
+        let back = DxDecimal::from_decimal128_bytes(wide.to_decimal128_bytes().unwrap()).unwrap();
+        assert_eq!((back, back.coeff() < wide.coeff()), (wide, true));
+        assert!(format!("1{}1", "0".repeat(33)).parse::<DxDecimal>().unwrap().to_decimal128_bytes().is_none());
+        assert!(DxDecimal::new(0, -7000).to_decimal128_bytes().is_some());
+        let mut inf = [0u8; 16];
+        inf[15] = 0x78;
+        assert!(DxDecimal::from_decimal128_bytes(inf).is_none());
+    }
+
+    #[test]
+    fn keep_number_text() {
+        let opts = ParseOptions { keep_number_text: true, ..Default::default() };
+        let src = r#"[1.10,1e400,123456789012345678901234567890123456789012,-0.0,7]"#;
+        let mut v = json::parseJsonWithOptions(src.as_bytes(), &opts).unwrap();
+        assert_eq!(v.to_string(), src);
+        assert_eq!(v.double_byIndex(0, 0.0), 1.1);
+        assert_eq!(v.decimal_byName("0").unwrap().scale(), 2);
+        assert_eq!(v.double_byName("1", 0.0), f64::INFINITY);
+        assert_eq!(v.try_int_byIndex(2), Err(errValueReson::VET_Overflow));
+        assert_eq!(v.int_byName("4", 0), 7);
+        assert_eq!(v.try_int_byIndex(0), Err(errValueReson::VET_TypeMismatch));
+        if let Some(n) = v.value_byIndex(4) {
+            n.setInt(8);
+        }
+        assert_eq!(v.to_string(), r#"[1.10,1e400,123456789012345678901234567890123456789012,-0.0,8]"#);
+    }
+
+    const JSONC_SRC: &str = r#"{
+    // 服务配置
+    "server": {
+        "host": "127.0.0.1",  /* 本机 */
+        "port": 8080 // 端口
+    },
+    "tags": ["a", "b"],
+    "debug": false,
+}
+"#;
+
+    #[test]
+    fn jsondoc_replace_keeps_formatting() {
+        let mut doc = JsonDocument::parse(JSONC_SRC).unwrap();
+        assert_eq!(doc.root().get_byPointer("/server/port").unwrap().as_int(), 8080);
+        assert!(doc.setKeyValue("/server/port", DxValue::Int(9090)));
+        assert!(doc.setKeyString("/server/host", "0.0.0.0"));
+        assert_eq!(
+            doc.as_str(),
+            JSONC_SRC.replace("8080", "9090").replace("127.0.0.1", "0.0.0.0")
+        );
+        assert!(!doc.setKeyValue("/missing/x", DxValue::Int(1)));
+        assert!(!doc.setKeyValue("/tags/5", DxValue::Int(1)));
+    }
+
+    #[test]
+    fn jsondoc_insert_and_remove() {
+        let mut doc = JsonDocument::parse(JSONC_SRC).unwrap();
+        assert!(doc.setKeyValue("/server/timeout", DxValue::Int(30)));
+        assert!(doc.setKeyValue("/level", DxValue::String("info".to_string())));
+        assert!(doc.setKeyString("/tags/2", "c"));
+        assert_eq!(
+            doc.as_str(),
+            r#"{
+    // 服务配置
+    "server": {
+        "host": "127.0.0.1",  /* 本机 */
+        "port": 8080, // 端口
+        "timeout": 30
+    },
+    "tags": ["a", "b", "c"],
+    "debug": false,
+    "level": "info",
+}
+"#
+        );
+        assert!(doc.removeKey("/server/timeout"));
+        assert!(doc.removeKey("/level"));
+        assert!(doc.removeKey("/tags/2"));
+        assert_eq!(doc.as_str(), JSONC_SRC);
+        assert!(doc.removeKey("/server/host"));
+        assert!(doc.removeKey("/tags/0"));
+        assert_eq!(
+            doc.as_str(),
+            r#"{
+    // 服务配置
+    "server": {
+        "port": 8080 // 端口
+    },
+    "tags": ["b"],
+    "debug": false,
+}
+"#
+        );
+        let mut empty = JsonDocument::parse("{\n}").unwrap();
+        assert!(empty.setKeyValue("/a", DxValue::Int(1)));
+        assert_eq!(empty.as_str(), "{\n  \"a\": 1\n}");
+
+        let mut commented = JsonDocument::parse("{\n  \"a\": 1 /* note\n  more */\n}").unwrap();
+        assert!(commented.setKeyValue("/b", DxValue::Double(0.125)));
+        assert_eq!(commented.as_str(), "{\n  \"a\": 1, /* note\n  more */\n  \"b\": 0.125\n}");
+        assert!(commented.setKeyValue("/a", DxValue::Double(1e-7)));
+        assert_eq!(commented.root().get_byPointer("/a").unwrap().as_double(), 1e-7);
+        assert!(!commented.setKeyValue("/a", DxValue::Double(f64::NAN)));
+    }
+
+    #[test]
+    fn jsonc_and_json5() {
+        let strict = ParseOptions::default();
+        let cases: [(&str, errJsonReson); 8] = [
+            ("[1, // c\n2]", errJsonReson::JET_Comment),
+            ("[1,2,]", errJsonReson::JET_TrailingComma),
+            ("{a:1}", errJsonReson::JET_UnquotedKey),
+            ("['a']", errJsonReson::JET_SingleQuote),
+            ("[0x1F]", errJsonReson::JET_HexNumber),
+            ("[-Infinity]", errJsonReson::JET_NonFinite),
+            ("[.5]", errJsonReson::JET_NumberFormat),
+            ("[\"a\\x41\"]", errJsonReson::JET_BadEscape),
+        ];
+        for (src, reson) in cases.iter() {
+            let err = json::parseJsonWithOptions(src.as_bytes(), &strict).unwrap_err();
+            assert_eq!(err.reson(), *reson, "{}", src);
+        }
+        let v = json::parseJsonWithOptions(b"{/* c */ \"a\": [1,2,], }", &ParseOptions::jsonc()).unwrap();
+        assert_eq!(v.to_string(), r#"{"a": [1,2]}"#);
+        let err = json::parseJsonWithOptions(b"{a: 1}", &ParseOptions::jsonc()).unwrap_err();
+        assert_eq!((err.reson(), err.pos()), (errJsonReson::JET_UnquotedKey, 1));
+
+        let src = "{\n  // JSON5\n  unquoted: 'and you can quote me on that',\n  hex: 0xDECAF,\n  leading: .8675309, trailing: 8675309., plus: +1,\n  inf: -Infinity, nan: NaN,\n  'back\\'s': 'line 1 \\\n line 2',\n}";
+        let v = json::parseJsonWithOptions(src.as_bytes(), &ParseOptions::json5()).unwrap();
+        assert_eq!(v.string_byName("unquoted", ""), "\"and you can quote me on that\"");
+        assert_eq!(v.int_byName("hex", 0), 0xDECAF);
+        assert_eq!(v.double_byName("leading", 0.0), 0.8675309);
+        assert_eq!(v.int_byName("trailing", 0), 8675309);
+        assert_eq!(v.int_byName("plus", 0), 1);
+        assert_eq!(v.double_byName("inf", 0.0), f64::NEG_INFINITY);
+        assert!(v.double_byName("nan", 0.0).is_nan());
+        assert_eq!(v.string_byName("back's", ""), "\"line 1  line 2\"");
+        //非有限值输出为JSON5的写法，严格JSON输出时报错
+        let specials = json::parseJsonWithOptions(b"[Infinity,-Infinity,NaN]", &ParseOptions::json5()).unwrap();
+        assert_eq!(specials.to_string(), "[Infinity,-Infinity,NaN]");
+        assert!(json::parseJsonWithOptions(specials.to_string().as_bytes(), &ParseOptions::json5()).is_ok());
+        assert_eq!(specials.to_json().unwrap_err(), errValueReson::VET_NonFinite);
+    }
+
+    #[test]
+    fn ndjson_lines() {
+        let src = "{\"id\":1}\r\n\n{\"id\":2,}\n[3]\n";
+        let results: Vec<_> = NdjsonReader::new(src.as_bytes()).collect();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap().int_byName("id", 0), 1);
+        let err = results[1].as_ref().unwrap_err();
+        assert_eq!((err.line(), err.pos(), err.reson()), (3, 19, errJsonReson::JET_TrailingComma));
+        assert!(results[2].is_ok());
+        //与并行解析的错误一致
+        let parallel = parallel::parseNdjsonParallel(src.as_bytes(), &ParseOptions::default(), 2);
+        let perr = parallel[1].as_ref().unwrap_err();
+        assert_eq!((perr.line(), perr.pos()), (err.line(), err.pos()));
+        let stopped: Vec<_> = NdjsonReader::new(src.as_bytes()).stop_on_error(true).collect();
+        assert_eq!(stopped.len(), 2);
+        //超过max_total_bytes的行只读入上限加1个字节，后面的行继续读取
+        let long = format!("[1]\n\"{}\"\n[2]\n", "x".repeat(100_000));
+        let opts = ParseOptions { max_total_bytes: 64, ..Default::default() };
+        let mut reader = NdjsonReader::with_options(std::io::BufReader::with_capacity(16, long.as_bytes()), opts);
+        assert!(reader.next().unwrap().is_ok());
+        let err = reader.next().unwrap().unwrap_err();
+        assert_eq!((err.reson(), err.line(), err.pos()), (errJsonReson::JET_InputTooLarge, 2, 4 + 64));
+        assert_eq!(reader.next().unwrap().unwrap().to_string(), "[2]");
+        assert!(reader.next().is_none());
+
+        let mut w = NdjsonWriter::new(Vec::new());
+        for v in results.into_iter().flatten() {
+            w.write(&v).unwrap();
+        }
+        w.write(&DxValue::Double(0.125)).unwrap();
+        assert!(w.write(&DxValue::Double(f64::NAN)).is_err());
+        let out = String::from_utf8(w.into_inner()).unwrap();
+        assert_eq!(out, "{\"id\":1}\n[3]\n0.125\n");
+    }
+
+    #[test]
+    fn parallel_parsing() {
+        let mut src = String::new();
+        for i in 0..100 {
+            src.push_str(&format!("{{\"id\":{}}}\n", i));
+        }
+        let bad = src.len();
+        src.push_str("{\"id\":}\n[1]");
+        let results = parallel::parseNdjsonParallel(src.as_bytes(), &ParseOptions::default(), 4);
+        assert_eq!(results.len(), 102);
+        assert_eq!(results[57].as_ref().unwrap().int_byName("id", 0), 57);
+        let err = results[100].as_ref().unwrap_err();
+        assert_eq!((err.line(), err.pos()), (101, bad as isize + 6));
+
+        let arr = format!("[{}]", (0..1000).map(|i| format!("{{\"n\":[{}]}}", i)).collect::<Vec<_>>().join(","));
+        let v = parallel::parseArrayParallel(arr.as_bytes(), &ParseOptions::default(), 4).unwrap();
+        assert_eq!(v.to_string(), json::parseJson(arr.clone().into_bytes()).unwrap().to_string());
+        let err = parallel::parseArrayParallel(b"[1, 2, {\"a\" 3}]", &ParseOptions::default(), 2).unwrap_err();
+        assert_eq!((err.reson(), err.pos()), (errJsonReson::JET_NoKVSplit, 12));
+        let err = parallel::parseArrayParallel(b"[1, 2,]", &ParseOptions::default(), 2).unwrap_err();
+        assert_eq!(err.reson(), errJsonReson::JET_TrailingComma);
+        for src in [&b"[1 2]"[..], b"[1, {\"a\": [}, 3]", b"[\"\xff\", 1]"] {
+            let single = json::parseJsonWithOptions(src, &ParseOptions::default()).unwrap_err();
+            let err = parallel::parseArrayParallel(src, &ParseOptions::default(), 2).unwrap_err();
+            assert_eq!((err.reson(), err.pos()), (single.reson(), single.pos()));
+        }
+        //没有BOM的UTF-16按原始字节切分会切断编码单元
+        let utf16: Vec<u8> = "[\"中\", 2, 3]".encode_utf16().flat_map(|u| u.to_le_bytes()).collect();
+        let v = parallel::parseArrayParallel(&utf16, &ParseOptions::default(), 3).unwrap();
+        assert_eq!((v.string_byIndex(0, String::new()), v.int_byIndex(2, 0)), ("\"中\"".to_string(), 3));
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn load_mmap_file() {
+        let path = std::env::temp_dir().join(format!("rvalue_mmap_{}.json", std::process::id()));
+        std::fs::write(&path, br#"{"name":"mmap","n":[1,2,3]}"#).unwrap();
+        let file = path.to_str().unwrap();
+        let v = json::load_mmap(file).unwrap();
+        assert_eq!(v.to_string(), json::loadFromFile(file).unwrap().to_string());
+        std::fs::remove_file(&path).unwrap();
+        let err = json::load_mmap(file).unwrap_err();
+        assert_eq!(err.reson(), errJsonReson::JET_UnParse);
+    }
+
+    #[test]
+    fn borrowed_values() {
+        let src = br#"{"name":"plain","esc":"a\nb","n":[1,2.5,18446744073709551615],"ok":true}"#;
+        let v = json::parseJsonRef(src, &ParseOptions::default()).unwrap();
+        let name = v.get_byName("name").unwrap();
+        assert_eq!(name.as_str(), Some("plain"));
+        assert!(name.is_borrowed());
+        assert!(!v.get_byName("esc").unwrap().is_borrowed());
+        let n = v.get_byName("n").unwrap();
+        assert_eq!(n.get_byIndex(0).unwrap().as_i64(), Some(1));
+        assert_eq!(n.get_byIndex(1).unwrap(), &DxValueRef::Number("2.5".into()));
+        assert_eq!(v.get_byName("ok").unwrap().as_bool(), Some(true));
+        let owned = DxValue::from(&v);
+        assert_eq!(owned.string_byName("esc", ""), "\"a\\nb\"");
+        assert!(matches!(owned.get_byPointer("/n/0"), Some(DxValue::Int(1))));
+        assert!(matches!(owned.get_byPointer("/n/1"), Some(DxValue::Double(d)) if *d == 2.5));
+        assert!(matches!(owned.get_byPointer("/n/2"), Some(DxValue::UInt64(u64::MAX))));
+        assert!(owned.get_byName("ok").unwrap().as_bool());
+        //值类型相关的选项在转换时生效
+        let opts = ParseOptions { decimal: true, base64_keys: vec!["b".to_string()], ..Default::default() };
+        let typed = json::parseJsonRef(br#"{"b":"/wCA","d":1.10}"#, &opts).unwrap().to_value_with(&opts);
+        assert!(matches!(typed.get_byName("b"), Some(DxValue::Binary(b)) if b == &[0xff, 0x00, 0x80]));
+        assert!(matches!(typed.get_byName("d"), Some(DxValue::Decimal(d)) if d.to_string() == "1.10"));
+        let err = json::parseJsonRef(b"{\"a\":[1,]}", &ParseOptions::default()).unwrap_err();
+        assert_eq!((err.reson(), err.pos()), (errJsonReson::JET_TrailingComma, 8));
+        let v = json::parseJsonRef(b"{a: 'x', b: +.5, c: -Infinity}", &ParseOptions::json5()).unwrap();
+        let owned5 = v.to_value();
+        assert_eq!(owned5.double_byName("b", 0.0), 0.5);
+        assert_eq!(owned5.double_byName("c", 0.0), f64::NEG_INFINITY);
+    }
+
+    #[test]
+    fn lazy_raw_subtrees() {
+        let src = br#"{"id": 7, "payload": {"items": [1, 2, {"deep": true}], "note": "x"}, "tags": ["a"]}"#;
+        let opts = ParseOptions { lazy_depth: 1, ..Default::default() };
+        let mut v = json::parseJsonWithOptions(src, &opts).unwrap();
+        assert_eq!(v.int_byName("id", 0), 7);
+        assert!(matches!(v.get_byName("payload"), Some(DxValue::Raw(_))));
+        assert_eq!(v.to_string(), r#"{"id": 7,"payload": {"items": [1, 2, {"deep": true}], "note": "x"},"tags": ["a"]}"#);
+        //只读访问与不延迟解析时结果相同，节点保持为Raw
+        let eager = json::parseJson(src.to_vec()).unwrap();
+        for (path, len) in [("/payload", 2), ("/payload/items", 3), ("/tags", 1)] {
+            assert_eq!(v.get_byPointer(path).unwrap().len(), len, "{}", path);
+            assert_eq!(eager.get_byPointer(path).unwrap().len(), len, "{}", path);
+        }
+        assert!(v.get_byPointer("/payload/items/2/deep").unwrap().as_bool());
+        assert_eq!(v.get_byName("payload").unwrap().string_byName("note", ""), "\"x\"");
+        assert_eq!(v.get_byName("tags").unwrap().string_byIndex(0, String::new()), "\"a\"");
+        assert!(matches!(v.get_byName("payload"), Some(DxValue::Raw(_))));
+        let payload = v.value_byName("payload").unwrap();
+        assert_eq!(payload.string_byName("note", ""), "\"x\"");
+        assert!(matches!(payload.get_byName("items"), Some(DxValue::Raw(_))));
+        let items = payload.value_byName("items").unwrap();
+        assert_eq!(items.int_byIndex(1, 0), 2);
+        assert!(items.value_byIndex(2).unwrap().get_byName("deep").unwrap().as_bool());
+        let err = json::parseJsonWithOptions(br#"{"a": {"b": [1,]}}"#, &opts).unwrap_err();
+        assert_eq!((err.reson(), err.pos()), (errJsonReson::JET_TrailingComma, 15));
+        let mut raw = DxValue::Raw(DxRawJson::new(r#"{"k": 1}"#).unwrap());
+        raw.setKeyValue("m", DxValue::Int(2));
+        assert_eq!(raw.to_string(), r#"{"k": 1,"m": 2}"#);
+        assert!(DxRawJson::new("[1 2]").is_err());
+        //Raw的JSON类型取解析后的值，解析失败时看第一个字符
+        use crate::dxvalue::schema::typeName;
+        assert_eq!(typeName(&DxValue::Raw(DxRawJson::new(r#" "s""#).unwrap())), "string");
+        assert_eq!(typeName(&DxValue::Raw(DxRawJson::new("{}").unwrap())), "object");
+        let broken = DxValue::Raw(DxRawJson::from_parts(r#" {"a": "#, Arc::new(ParseOptions::default())));
+        assert_eq!(typeName(&broken), "object");
+        let changes = broken.diff(&DxValue::newObject());
+        assert_eq!((changes.len(), changes[0].kind), (1, ChangeKind::Changed));
+    }
+
+    #[test]
+    fn simd_scan_matches_scalar() {
+        //在不同长度和位置放置目标字符，覆盖整块和剩余部分
+        for len in 0..80 {
+            for at in 0..=len {
+                let mut ws = vec![b' '; len];
+                ws.iter_mut().enumerate().for_each(|(i, c)| *c = b" \t\r\n"[i % 4]);
+                if at < len {
+                    ws[at] = b'x';
+                }
+                assert_eq!(scan::skipWhitespace(&ws), scan::scalar::skipWhitespace(&ws));
+                let mut s = vec![b'a'; len];
+                if at < len {
+                    s[at] = [b'"', b'\\', 0x1F, b'\''][at % 4];
+                }
+                assert_eq!(scan::findStringSpecial(&s, b'"'), scan::scalar::findStringSpecial(&s, b'"'));
+                assert_eq!(scan::findStringSpecial(&s, b'\''), scan::scalar::findStringSpecial(&s, b'\''));
+                #[cfg(target_arch = "x86_64")]
+                unsafe {
+                    assert_eq!(scan::x86::skipWhitespaceSse2(&ws), scan::scalar::skipWhitespace(&ws));
+                    assert_eq!(scan::x86::findStringSpecialSse2(&s, b'"'), scan::scalar::findStringSpecial(&s, b'"'));
+                }
+                if at < len {
+                    s[at] = 0xFF;
+                }
+                assert_eq!(scan::validateUtf8(&s).err(), std::str::from_utf8(&s).err().map(|e| e.valid_up_to()));
+            }
+        }
+        //多字节序列放在块内和跨块的各个位置，包括各类非法序列和截断
+        let samples: [&[u8]; 16] = [
+            "é".as_bytes(), "不".as_bytes(), "😀".as_bytes(), "\u{10FFFF}".as_bytes(), "\u{FFFF}".as_bytes(),
+            &[0xC0, 0x80], &[0xC1, 0xBF], &[0xE0, 0x80, 0x80], &[0xED, 0xA0, 0x80], &[0xF0, 0x80, 0x80, 0x80],
+            &[0xF4, 0x90, 0x80, 0x80], &[0xF5, 0x80, 0x80, 0x80], &[0x80], &[0xE4, 0xB8], &[0xF0, 0x9F, 0x98], &[0xE4, 0x41, 0x80],
+        ];
+        for prefix in 0..70 {
+            for a in samples.iter() {
+                for b in samples.iter().take(5) {
+                    let mut s = vec![b'a'; prefix];
+                    s.extend_from_slice(a);
+                    s.extend_from_slice("中文".as_bytes());
+                    s.extend_from_slice(b);
+                    let expect = std::str::from_utf8(&s).err().map(|e| e.valid_up_to());
+                    assert_eq!(scan::validateUtf8(&s).err(), expect);
+                    #[cfg(target_arch = "x86_64")]
+                    {
+                        if s.len() >= 32 && scan::x86::hasAvx2() {
+                            assert_eq!(unsafe { scan::x86::validateUtf8Avx2(&s) }, expect.is_none());
+                        }
+                    }
+                    s.truncate(s.len() - 1);
+                    assert_eq!(scan::validateUtf8(&s).err(), std::str::from_utf8(&s).err().map(|e| e.valid_up_to()));
+                }
+            }
+        }
+        let text = "{\"名字\": \"不得闲\",                     \"k\": \"0123456789abcdefghijklmnopqrstuvwxyz\\n\"}";
+        let v = json::parseJson(text.as_bytes().to_vec()).unwrap();
+        assert_eq!(v.string_byName("名字", ""), "\"不得闲\"");
+        assert_eq!(v.string_byName("k", ""), "\"0123456789abcdefghijklmnopqrstuvwxyz\\n\"");
+    }
+
+    #[test]
+    fn input_encodings() {
+        let text = r#"{"名字":"不得闲","n":1}"#;
+        let utf16le: Vec<u8> = vec![0xFFu8, 0xFE].into_iter().chain(text.encode_utf16().flat_map(|u| u.to_le_bytes())).collect();
+        let utf16be: Vec<u8> = text.encode_utf16().flat_map(|u| u.to_be_bytes()).collect();
+        let utf32le: Vec<u8> = text.chars().flat_map(|c| (c as u32).to_le_bytes()).collect();
+        let (gbk, _, _) = encoding_rs::GBK.encode(text);
+        let expected = json::parseJson(text.as_bytes().to_vec()).unwrap().to_string();
+        for input in [utf16le.clone(), utf16be, utf32le] {
+            assert_eq!(json::parseJson(input).unwrap().to_string(), expected);
+        }
+        let opts = ParseOptions { encoding: InputEncoding::Gbk, ..Default::default() };
+        assert_eq!(json::parseJsonWithOptions(&gbk, &opts).unwrap().to_string(), expected);
+        //不经过转码的入口逐段校验字符串，转义之后的非法字节报告原文中的位置
+        let bad = b"[\"a\\n\xFFb\"]";
+        let err = json::parseJsonRef(bad, &ParseOptions::default()).unwrap_err();
+        assert_eq!((err.reson(), err.pos()), (errJsonReson::JET_Invalidate, 5));
+        assert_eq!(json::validateJson(bad, &ParseOptions::jsonc()).unwrap_err().pos(), 5);
+        let v = json::parseJsonWithOptions("['\\é\\n']".as_bytes(), &ParseOptions::json5()).unwrap();
+        assert_eq!(v.to_json().unwrap(), "[\"é\\n\"]");
+        //自动识别不会猜测GBK，报告第一个不合法的UTF-8字节
+        let err = json::parseJson(gbk.to_vec()).unwrap_err();
+        assert_eq!((err.reson(), err.pos()), (errJsonReson::JET_Encoding, 2));
+        let mut bad = br#"{"k":"ab"}"#.to_vec();
+        bad[7] = 0xFF;
+        let err = json::parseJson(bad).unwrap_err();
+        assert_eq!((err.reson(), err.pos()), (errJsonReson::JET_Encoding, 7));
+
+        //出错的字符3下标为5，在UTF-16输入中位于 2 + 5 * 2
+        let bad: Vec<u8> = vec![0xFFu8, 0xFE].into_iter().chain("[1,2 3]".encode_utf16().flat_map(|u| u.to_le_bytes())).collect();
+        let err = json::parseJson(bad).unwrap_err();
+        assert_eq!((err.reson(), err.pos()), (errJsonReson::JET_NoValueSplit, 12));
+        //孤立的低位代理
+        let mut bad = utf16le;
+        bad.splice(6..8, [0x00, 0xDC]);
+        let err = json::parseJson(bad).unwrap_err();
+        assert_eq!((err.reson(), err.pos()), (errJsonReson::JET_Encoding, 6));
+    }
+
+    #[test]
+    fn parse_limits() {
+        let deep = "[".repeat(100_000);
+        let err = json::parseJson(deep.clone().into_bytes()).unwrap_err();
+        assert_eq!((err.reson(), err.pos()), (errJsonReson::JET_TooDeep, 128));
+        let err = json::parseJsonRef(deep.as_bytes(), &ParseOptions::default()).unwrap_err();
+        assert_eq!(err.reson(), errJsonReson::JET_TooDeep);
+        let ok = format!("{}{}", "[".repeat(128), "]".repeat(128));
+        assert!(json::parseJson(ok.into_bytes()).is_ok());
+
+        let opts = ParseOptions {
+            max_string_len: 4,
+            max_container_len: 3,
+            max_total_bytes: 64,
+            ..Default::default()
+        };
+        let err = json::parseJsonWithOptions(br#"{"k": "abcde"}"#, &opts).unwrap_err();
+        assert_eq!((err.reson(), err.pos()), (errJsonReson::JET_StringTooLong, 6));
+        assert!(json::parseJsonWithOptions(br#"{"k": "a\nc"}"#, &opts).is_ok());
+        let err = json::parseJsonWithOptions(b"[1,2,3,4]", &opts).unwrap_err();
+        assert_eq!((err.reson(), err.pos()), (errJsonReson::JET_ContainerTooLarge, 7));
+        let err = parallel::parseArrayParallel(b"[1,2,3,4]", &opts, 2).unwrap_err();
+        assert_eq!(err.reson(), errJsonReson::JET_ContainerTooLarge);
+        let err = json::parseJsonWithOptions(&[b' '; 65], &opts).unwrap_err();
+        assert_eq!(err.reson(), errJsonReson::JET_InputTooLarge);
+    }
+
+    #[test]
+    fn duplicate_keys() {
+        let src = br#"{"a": 1, "b": 0, "a": 2, "a": 3}"#;
+        let parse = |policy| json::parseJsonWithOptions(src, &ParseOptions { duplicate_keys: policy, ..Default::default() });
+        let all = parse(DuplicateKeys::KeepAll).unwrap();
+        assert_eq!(all.int_byName("a", 0), 1);
+        let values: Vec<isize> = all.values_byName("a").iter().map(|v| v.as_int()).collect();
+        assert_eq!(values, vec![1, 2, 3]);
+        assert_eq!(parse(DuplicateKeys::First).unwrap().to_string(), r#"{"a": 1,"b": 0}"#);
+        assert_eq!(parse(DuplicateKeys::Last).unwrap().to_string(), r#"{"a": 3,"b": 0}"#);
+        let err = parse(DuplicateKeys::Error).unwrap_err();
+        assert_eq!((err.reson(), err.pos()), (errJsonReson::JET_DuplicateKey, 17));
+
+        //成员较多时使用HashMap查找
+        let big = format!("{{{},\"k7\":-1}}", (0..100).map(|i| format!("\"k{}\":{}", i, i)).collect::<Vec<_>>().join(","));
+        let opts = ParseOptions { duplicate_keys: DuplicateKeys::Last, ..Default::default() };
+        let v = json::parseJsonWithOptions(big.as_bytes(), &opts).unwrap();
+        assert_eq!((v.len(), v.int_byName("k7", 0)), (100, -1));
+        let v = json::parseJsonRef(big.as_bytes(), &opts).unwrap();
+        assert_eq!(v.get_byName("k7").unwrap().as_i64(), Some(-1));
+    }
+
+    #[test]
+    fn source_spans() {
+        let src = "{\n  \"name\": \"测试\",\n  \"server\": {\n    \"port\": \"8x\",\n    \"hosts\": [1, 2]\n  }\n}";
+        let (v, map) = json::parseJsonWithSpans(src.as_bytes(), &ParseOptions::default()).unwrap();
+        let map = map.with_file("config.json");
+        assert_eq!(v.get_byPointer("/server").unwrap().try_int_byName("port"), Err(errValueReson::VET_TypeMismatch));
+        assert_eq!(map.describe("/server/port").unwrap(), "config.json line 4 column 5");
+        let port = map.get("/server/port").unwrap();
+        assert_eq!((port.start.line, port.start.column, port.end.column), (4, 13, 17));
+        assert_eq!(&src[port.start.offset..port.end.offset], "\"8x\"");
+        //列按字符计
+        assert_eq!(map.get("/name").unwrap().end.column, 15);
+        let root = map.get("").unwrap();
+        assert_eq!((root.key, root.start.line, root.end.line, root.end.column), (None, 1, 7, 2));
+        assert_eq!(map.get("/server/hosts/1").unwrap().start.column, 18);
+        let paths: Vec<&str> = map.spans().iter().map(|s| s.path.as_str()).collect();
+        assert_eq!(paths, vec!["", "/name", "/server", "/server/port", "/server/hosts", "/server/hosts/0", "/server/hosts/1"]);
+
+        let err = json::parseJson(b"{\n  \"a\": 1,\n  \"b\" 2\n}".to_vec()).unwrap_err();
+        let pos = sourcemap::lineColumn(b"{\n  \"a\": 1,\n  \"b\" 2\n}", err.pos() as usize);
+        assert_eq!((pos.line, pos.column), (3, 7));
+    }
+
+    #[test]
+    fn canonical_json() {
+        assert_eq!(sha256::toHex(&sha256::digest(b"abc")), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        let mut h = sha256::Sha256::new();
+        for chunk in vec![b'a'; 1000].chunks(7) {
+            h.update(chunk);
+        }
+        assert_eq!(h.finish(), sha256::digest(&[b'a'; 1000]));
+        assert_eq!(sha256::toHex(&sha256::digest(b"")), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+
+        //RFC 8785 附录B中的数字
+        let numbers = vec![
+            (0.0, "0"), (-0.0, "0"), (5e-324, "5e-324"), (1.7976931348623157e308, "1.7976931348623157e+308"),
+            (9007199254740992.0, "9007199254740992"), (1e21, "1e+21"), (1e20, "100000000000000000000"),
+            (0.000001, "0.000001"), (1e-7, "1e-7"), (-1.5e-7, "-1.5e-7"), (333333333.3333333, "333333333.3333333"),
+            (123.456, "123.456"),
+        ];
+        for (v, text) in numbers {
+            assert_eq!(canonical::formatNumber(v).unwrap(), text);
+        }
+        assert_eq!(canonical::formatNumber(f64::NAN), Err(errValueReson::VET_NonFinite));
+
+        let v = json::parseJson(r#"{"numbers":[333333333.33333329,1E30,4.50,2e-3,0.000000000000000000000000001],"string":"\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/","literals":[null,true,false]}"#.as_bytes().to_vec()).unwrap();
+        assert_eq!(v.to_canonical().unwrap(), r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"string":"€$\u000f\nA'B\"\\\\\"/"}"#);
+        //按UTF-16编码单元排序：\u{10000}的代理对0xD800排在\u{ff61}之前
+        let v = json::parseJson("{\"\u{ff61}\":1,\"\u{10000}\":2,\"b\":3,\"a\":4,\"a\":5}".as_bytes().to_vec()).unwrap();
+        assert_eq!(v.to_canonical().unwrap(), "{\"a\":4,\"b\":3,\"\u{10000}\":2,\"\u{ff61}\":1}");
+
+        //键的顺序和数字的存储类型不影响哈希
+        let mut a = DxValue::newObject();
+        a.setKeyValue("x", DxValue::Int(1));
+        a.setKeyValue("y", DxValue::Float(0.1));
+        let b = json::parseJson(br#"{ "y": 0.1, "x": 1.0 }"#.to_vec()).unwrap();
+        assert_eq!(a.content_hash(), b.content_hash());
+        assert_ne!(a.content_hash(), json::parseJson(br#"{"x":1,"y":0.2}"#.to_vec()).unwrap().content_hash());
+        assert_eq!(DxValue::Double(f64::INFINITY).content_hash(), Err(errValueReson::VET_NonFinite));
+    }
+
+    #[test]
+    fn schema_validation() {
+        let schema = json::parseJson(br##"{
+            "$defs": {
+                "port": {"type": "integer", "minimum": 1, "maximum": 65535},
+                "host": {"$anchor": "host", "type": "string", "format": "hostname"}
+            },
+            "type": "object",
+            "required": ["name", "server"],
+            "properties": {
+                "name": {"type": "string", "minLength": 2, "pattern": "^[a-z]+$"},
+                "server": {
+                    "type": "object",
+                    "properties": {"port": {"$ref": "#/$defs/port"}, "host": {"$ref": "#host"}},
+                    "additionalProperties": false
+                },
+                "mode": {"enum": ["dev", "prod"]},
+                "tags": {"type": "array", "items": {"type": "string"}, "uniqueItems": true, "maxItems": 3},
+                "ratio": {"oneOf": [{"type": "integer"}, {"multipleOf": 0.5}]},
+                "id": {"not": {"const": 0}, "anyOf": [{"type": "integer"}, {"type": "string", "format": "uuid"}]}
+            }
+        }"##.to_vec()).unwrap();
+        let validator = Validator::new(&schema).unwrap();
+        let ok = json::parseJson(br#"{"name": "svc", "server": {"port": 8080.0, "host": "db.local"}, "mode": "dev",
+            "tags": ["a", "b"], "ratio": 1.5, "id": "123e4567-e89b-12d3-a456-426614174000"}"#.to_vec()).unwrap();
+        assert_eq!(validator.validate(&ok), Ok(()));
+
+        let bad = json::parseJson(br#"{"name": "X", "server": {"port": 70000, "host": "-bad-", "extra": 1}, "mode": "test",
+            "tags": ["a", "a"], "ratio": 2, "id": 0}"#.to_vec()).unwrap();
+        let errs = validator.validate(&bad).unwrap_err();
+        let got: Vec<(&str, &str)> = errs.iter().map(|e| (e.instance_path.as_str(), e.schema_path.as_str())).collect();
+        assert_eq!(got, vec![
+            ("/name", "/properties/name/minLength"),
+            ("/name", "/properties/name/pattern"),
+            ("/server/port", "/$defs/port/maximum"),
+            ("/server/host", "/$defs/host/format"),
+            ("/server/extra", "/properties/server/additionalProperties"),
+            ("/mode", "/properties/mode/enum"),
+            ("/tags", "/properties/tags/uniqueItems"),
+            ("/ratio", "/properties/ratio/oneOf"),
+            ("/id", "/properties/id/not"),
+        ]);
+        assert_eq!(errs[2].keyword, "maximum");
+        let missing = validator.validate(&json::parseJson(br#"{"name": "ab"}"#.to_vec()).unwrap()).unwrap_err();
+        assert_eq!((missing.len(), missing[0].keyword, missing[0].message.as_str()), (1, "required", "缺少属性server"));
+        assert!(!validator.is_valid(&DxValue::Int(1)));
+
+        //编译时检查引用和正则
+        let err = Validator::new(&json::parseJson(br##"{"items": {"$ref": "#/$defs/missing"}}"##.to_vec()).unwrap()).err().unwrap();
+        assert_eq!((err.keyword, err.schema_path.as_str()), ("$ref", "/items/$ref"));
+        assert!(Validator::new(&json::parseJson(br#"{"pattern": "("}"#.to_vec()).unwrap()).is_err());
+        //enum、const、uniqueItems按精确值比较
+        let ids = Validator::new(&json::parseJson(br#"{"enum": [9007199254740993, 1.50], "uniqueItems": true}"#.to_vec()).unwrap()).unwrap();
+        assert!(!ids.is_valid(&json::parseJson(b"9007199254740992".to_vec()).unwrap()));
+        assert!(ids.is_valid(&json::parseJson(b"9007199254740993".to_vec()).unwrap()));
+        assert!(ids.is_valid(&DxValue::Decimal(DxDecimal::new(15, -1))));
+        assert!(ids.is_valid(&DxValue::Double(1.5)));
+        let unique = Validator::new(&json::parseJson(br#"{"uniqueItems": true}"#.to_vec()).unwrap()).unwrap();
+        assert!(unique.is_valid(&json::parseJson(b"[9007199254740993, 9007199254740992]".to_vec()).unwrap()));
+        assert!(!unique.is_valid(&json::parseJson(br#"[{"a": 1, "b": [2.0]}, {"b": [2], "a": 1.0}]"#.to_vec()).unwrap()));
+        //属性名和关键字同名时仍然是子schema
+        let named = Validator::new(&json::parseJson(br#"{"properties": {"default": {"type": "string", "pattern": "^a"}, "enum": {"type": "integer"}}}"#.to_vec()).unwrap()).unwrap();
+        let errs = named.validate(&json::parseJson(br#"{"default": "b", "enum": 1}"#.to_vec()).unwrap()).unwrap_err();
+        assert_eq!((errs.len(), errs[0].schema_path.as_str()), (1, "/properties/default/pattern"));
+        let err = Validator::new(&json::parseJson(br##"{"$defs": {"const": {"$ref": "#/nowhere"}}}"##.to_vec()).unwrap()).err().unwrap();
+        assert_eq!(err.schema_path, "/$defs/const/$ref");
+        //enum中的值只是数据
+        assert!(Validator::new(&json::parseJson(br##"{"enum": [{"$ref": "#/nowhere", "pattern": "("}]}"##.to_vec()).unwrap()).is_ok());
+        //递归的深度只受数据限制，循环引用在同一个实例位置上重复时才报错
+        let linked = Validator::new(&json::parseJson(br##"{"type": "object", "properties": {"next": {"$ref": "#"}, "v": {"type": "integer"}}}"##.to_vec()).unwrap()).unwrap();
+        let deep = format!("{}{}", "{\"next\": ".repeat(100), "{\"v\": 1}".to_string() + &"}".repeat(100));
+        assert!(linked.is_valid(&json::parseJson(deep.clone().into_bytes()).unwrap()));
+        let errs = linked.validate(&json::parseJson(deep.replace("\"v\": 1", "\"v\": \"x\"").into_bytes()).unwrap()).unwrap_err();
+        assert_eq!((errs.len(), errs[0].instance_path.len()), (1, "/next".len() * 100 + "/v".len()));
+        for cyclic in [r##"{"$ref": "#"}"##, r##"{"anyOf": [{"$ref": "#"}, {"$ref": "#"}]}"##, r##"{"$defs": {"a": {"$ref": "#/$defs/b"}, "b": {"allOf": [{"$ref": "#/$defs/a"}]}}, "$ref": "#/$defs/a"}"##] {
+            let errs = Validator::new(&json::parseJson(cyclic.as_bytes().to_vec()).unwrap()).unwrap().validate(&DxValue::Int(1)).unwrap_err();
+            //anyOf只报告自身，分支中的循环引用错误不单独列出
+            assert!(errs.iter().any(|e| e.message.starts_with("循环引用") || e.keyword == "anyOf"), "{}", cyclic);
+        }
+        //嵌入$id的子schema中，片段引用相对该资源解析
+        let bundled = Validator::new(&json::parseJson(br##"{"$id": "https://x/root", "$defs": {
+            "n": {"type": "string"},
+            "item": {"$id": "item", "$defs": {"n": {"type": "integer"}, "m": {"$anchor": "m", "minimum": 0}}, "$ref": "#/$defs/n", "items": {"$ref": "#m"}}
+        }, "properties": {"a": {"$ref": "item"}, "b": {"$ref": "https://x/item#/$defs/n"}, "c": {"$ref": "#/$defs/n"}}}"##.to_vec()).unwrap()).unwrap();
+        assert!(bundled.is_valid(&json::parseJson(br#"{"a": 1, "b": 2, "c": "s"}"#.to_vec()).unwrap()));
+        let errs = bundled.validate(&json::parseJson(br#"{"a": "s", "c": 1}"#.to_vec()).unwrap()).unwrap_err();
+        let got: Vec<&str> = errs.iter().map(|e| e.schema_path.as_str()).collect();
+        assert_eq!(got, vec!["/$defs/item/$defs/n/type", "/$defs/n/type"]);
+        assert!(Validator::new(&json::parseJson(br##"{"$defs": {"item": {"$id": "https://x/item", "$ref": "#m"}}, "$anchor": "m"}"##.to_vec()).unwrap()).is_err());
+        //递归schema
+        let tree =Validator::new(&json::parseJson(br##"{"type": "object", "properties": {"children": {"type": "array", "items": {"$ref": "#"}}}, "required": ["v"]}"##.to_vec()).unwrap()).unwrap();
+        let errs = tree.validate(&json::parseJson(br#"{"v": 1, "children": [{"v": 2, "children": [{}]}]}"#.to_vec()).unwrap()).unwrap_err();
+        assert_eq!((errs[0].instance_path.as_str(), errs[0].schema_path.as_str()), ("/children/0/children/0", "/required"));
+    }
+
+    #[test]
+    fn schema_inference() {
+        let samples: Vec<DxValue> = vec![
+            r#"{"id": 1, "name": "a", "score": 1.5, "tags": ["x"], "at": "2024-01-02T03:04:05Z"}"#,
+            r#"{"id": 5000000000, "name": "b", "score": null, "tags": [], "at": "2024-02-02T00:00:00+08:00", "extra": true}"#,
+            r#"{"id": 3, "name": "c", "score": 2, "tags": [1, "y"], "at": "2024-03-02T00:00:00Z"}"#,
+        ].into_iter().map(|s| json::parseJson(s.as_bytes().to_vec()).unwrap()).collect();
+        let mut inferrer = SchemaInferrer::new();
+        samples.iter().for_each(|s| inferrer.add(s));
+        assert_eq!(inferrer.describe(), "{id: int64, name: string, score: double | null, tags: [string | int32], at: datetime, extra?: bool}");
+
+        let schema = infer::inferSchema(&samples);
+        assert_eq!(schema.get_byPointer("/properties/id").unwrap().to_string(), r#"{"type": "integer","format": "int64"}"#);
+        assert_eq!(schema.get_byPointer("/properties/score/type").unwrap().to_string(), r#"["number","null"]"#);
+        assert_eq!(schema.get_byPointer("/properties/tags/items/type").unwrap().to_string(), r#"["string","integer"]"#);
+        assert_eq!(schema.get_byPointer("/required").unwrap().to_string(), r#"["id","name","score","tags","at"]"#);
+        //推断出的schema能校验所有样本
+        let validator = Validator::new(&schema).unwrap();
+        assert!(samples.iter().all(|s| validator.is_valid(s)));
+        assert!(!validator.is_valid(&json::parseJson(br#"{"id": "1"}"#.to_vec()).unwrap()));
+
+        let mut inferrer = SchemaInferrer::new();
+        inferrer.add(&DxValue::UInt64(u64::MAX));
+        inferrer.add(&DxValue::Binary(vec![1, 2]));
+        assert_eq!(inferrer.describe(), "binary | uint64");
+        assert_eq!(inferrer.samples(), 2);
+    }
+
+    #[test]
+    fn rust_codegen() {
+        let schema = json::parseJson(br##"{
+            "$defs": {"node": {"type": "object", "properties": {"name": {"type": "string"}, "children": {"type": "array", "items": {"$ref": "#/$defs/node"}}}, "required": ["name"]}},
+            "type": "object",
+            "properties": {
+                "root": {"$ref": "#/$defs/node"},
+                "maxCount": {"type": ["integer", "null"], "format": "int32"},
+                "type": {"type": "string", "format": "date-time"},
+                "tags": {"type": "array", "items": {"type": "string"}},
+                "any": {"type": ["string", "integer"]}
+            },
+            "required": ["root", "maxCount", "type"]
+        }"##.to_vec()).unwrap();
+        let code = codegen::rustFromSchema(&schema, &CodegenOptions { root_name: "tree_doc".to_string(), ..Default::default() });
+        for line in vec![
+            "use rvalue::dxvalue::{DxDateTime, DxValue};",
+            "pub struct TreeDoc {",
+            "    pub root: Node,",
+            "    /// json: \"maxCount\"\n    pub max_count: Option<i32>,",
+            "    pub r#type: DxDateTime,",
+            "    pub tags: Option<Vec<String>>,",
+            "    pub any: Option<DxValue>,",
+            "pub struct Node {",
+            "    pub children: Option<Vec<Node>>,",
+            "            max_count: FromDxValue::from_dxvalue(v.get_byName(\"maxCount\")?)?,",
+            "            tags: match v.get_byName(\"tags\") { Some(x) => FromDxValue::from_dxvalue(x)?, None => None },",
+            "        v.setKeyValue(\"type\", self.r#type.to_dxvalue());",
+        ] {
+            assert!(code.contains(line), "{}\n----\n{}", line, code);
+        }
+        assert_eq!(code.matches("pub struct Node {").count(), 1);
+        //根结构体在最前面
+        assert!(code.find("pub struct TreeDoc").unwrap() < code.find("pub struct Node").unwrap());
+
+        let samples = vec![json::parseJson(br#"{"ids": [1, 2], "nested": {"ok": true}}"#.to_vec()).unwrap()];
+        let code = codegen::rustFromSamples(&samples, &CodegenOptions::default());
+        assert!(code.contains("pub struct Root {\n    pub ids: Vec<i32>,\n    pub nested: Nested,\n}"));
+        assert!(code.starts_with("use rvalue::dxvalue::DxValue;"));
+        //根不是结构体时仍然有root_name命名的类型
+        let code = codegen::rustFromSamples(&[json::parseJson(br#"[{"a": 1}]"#.to_vec()).unwrap()], &CodegenOptions::default());
+        assert!(code.contains("pub type Root = Vec<RootItem>;") && code.contains("pub struct RootItem {"), "{}", code);
+
+        //生成的代码使用的转换
+        let v = json::parseJson(br#"[1, null, 3]"#.to_vec()).unwrap();
+        let ids: Vec<Option<i32>> = FromDxValue::from_dxvalue(&v).unwrap();
+        assert_eq!(ids, vec![Some(1), None, Some(3)]);
+        assert_eq!(ids.to_dxvalue().to_string(), "[1,null,3]");
+        assert_eq!(<Vec<i32>>::from_dxvalue(&v), None);
+        assert_eq!(i32::from_dxvalue(&DxValue::Int64(1 << 40)), None);
+        assert_eq!(i64::from_dxvalue(&DxValue::String("1".to_string())), None);
+    }
+
+    //由testdata/codegen_recursive.json生成，rust_codegen_fixture检查内容没有过时
+    mod codegen_fixture {
+        include!("../testdata/codegen_recursive.rs");
+    }
+
+    #[test]
+    fn rust_codegen_fixture() {
+        let schema = json::parseJson(include_bytes!("../testdata/codegen_recursive.json").to_vec()).unwrap();
+        let opts = CodegenOptions { root_name: "document".to_string(), crate_path: "crate".to_string(), ..Default::default() };
+        assert_eq!(codegen::rustFromSchema(&schema, &opts), include_str!("../testdata/codegen_recursive.rs"));
+
+        let text = r#"{"head": {"value": 1, "next": {"value": 2, "parent": {"target": {"value": 0}}}, "children": [{"value": 3}]}, "self": {"vec": {"n": 1.5}}}"#;
+        let v = json::parseJson(text.as_bytes().to_vec()).unwrap();
+        let doc = codegen_fixture::Document::from_dxvalue(&v).unwrap();
+        assert_eq!(doc.head.next.as_ref().unwrap().parent.as_ref().unwrap().target.value, 0);
+        assert_eq!(doc.self_.as_ref().and_then(|s| s.vec.as_ref()).and_then(|v| v.n), Some(1.5));
+        assert!(doc.option.is_none() && doc.string.is_none());
+        assert_eq!(doc.to_dxvalue().to_json().unwrap(), text.replace(": ", ":").replace(", ", ","));
+        //延迟解析的子节点
+        let lazy = json::parseJsonWithOptions(text.as_bytes(), &ParseOptions { lazy_depth: 1, ..Default::default() }).unwrap();
+        assert!(matches!(lazy.get_byName("head"), Some(DxValue::Raw(_))));
+        assert_eq!(codegen_fixture::Document::from_dxvalue(&lazy).unwrap().to_dxvalue().to_json().unwrap(), doc.to_dxvalue().to_json().unwrap());
+        //和关键字(包括保留的)同名的属性
+        let text = r#"{"head": {"value": 1}, "reserved": {"override": 1, "yield": 2, "final": "x", "typeof": true}}"#;
+        let doc = codegen_fixture::Document::from_dxvalue(&json::parseJson(text.as_bytes().to_vec()).unwrap()).unwrap();
+        let reserved = doc.reserved.as_ref().unwrap();
+        assert_eq!((reserved.r#override, reserved.r#yield, reserved.r#final.as_deref(), reserved.r#typeof), (Some(1), Some(2), Some("x"), Some(true)));
+        assert_eq!(doc.to_dxvalue().to_json().unwrap(), text.replace(": ", ":").replace(", ", ","));
+    }
+
+    #[test]
+    fn structural_diff() {
+        let a = json::parseJson(br#"{"name": "svc", "port": 80, "ratio": 0.5, "debug": true, "hosts": ["a", "b"],
+            "servers": [{"id": 1, "ip": "10.0.0.1"}, {"id": 2, "ip": "10.0.0.2"}], "old": null}"#.to_vec()).unwrap();
+        let b = json::parseJson(br#"{"hosts": ["a", "c", "d"], "name": "svc", "port": "80", "ratio": 0.5000001, "debug": false,
+            "servers": [{"id": 3, "ip": "10.0.0.3"}, {"id": 1.0, "ip": "10.0.0.9"}], "new": 1}"#.to_vec()).unwrap();
+        let summary = |changes: &[diff::Change]| changes.iter().map(|c| format!("{:?} {}", c.kind, c.path)).collect::<Vec<_>>();
+        assert_eq!(summary(&DxValue::diff(&a, &b)), vec![
+            "TypeChanged /port", "Changed /ratio", "Changed /debug", "Changed /hosts/1", "Added /hosts/2",
+            "Changed /servers/0/id", "Changed /servers/0/ip", "Changed /servers/1/id", "Changed /servers/1/ip",
+            "Removed /old", "Added /new",
+        ]);
+
+        let opts = DiffOptions { arrays: ArrayMode::ByKey("id".to_string()), tolerance: 1e-6, ..Default::default() };
+        let changes = a.diff_with(&b, &opts);
+        assert_eq!(summary(&changes), vec![
+            "TypeChanged /port", "Changed /debug", "Changed /hosts/1", "Added /hosts/2",
+            "Changed /servers/0/ip", "Removed /servers/1", "Added /servers/-", "Removed /old", "Added /new",
+        ]);
+        assert_eq!(changes[0].kind, ChangeKind::TypeChanged);
+        assert!(a.diff(&a.clone()).is_empty());
+
+        //只对指定路径按键配对
+        let opts = DiffOptions { array_keys: vec![("/servers".to_string(), "ip".to_string())], ..Default::default() };
+        assert_eq!(a.diff_with(&b, &opts).iter().filter(|c| c.path.starts_with("/servers")).count(), 4);
+
+        let text = diff::render(&changes[..2], false);
+        assert_eq!(text, "@@ /port @@ number -> string\n-80\n+\"80\"\n@@ /debug @@\n-true\n+false\n");
+        let colored = diff::render(&changes[1..2], true);
+        assert_eq!(colored, "\x1b[33m@@ /debug @@\x1b[0m\n\x1b[31m-true\x1b[0m\n\x1b[32m+false\x1b[0m\n");
+        assert_eq!(changes[4].to_string(), "@@ /servers/0/ip @@\n-\"10.0.0.1\"\n+\"10.0.0.9\"\n");
+        //数字按原值输出，不会显示成相同的文本
+        let changes = a.diff(&b);
+        assert_eq!(changes[1].to_string(), "@@ /ratio @@\n-0.5\n+0.5000001\n");
+        //超过2^53的键值不会被当作同一个元素
+        let ids = |s: &str| json::parseJson(s.as_bytes().to_vec()).unwrap();
+        let opts = DiffOptions { arrays: ArrayMode::ByKey("id".to_string()), ..Default::default() };
+        let changes = ids(r#"[{"id": 9007199254740993}]"#).diff_with(&ids(r#"[{"id": 9007199254740992}]"#), &opts);
+        assert_eq!(summary(&changes), vec!["Removed /0", "Added /-"]);
+    }
+
+    #[test]
+    fn three_way_merge() {
+        let parse = |s: &str| json::parseJson(s.as_bytes().to_vec()).unwrap();
+        let base = parse(r#"{"name": "svc", "port": 80, "log": {"level": "info", "file": "a.log"}, "hosts": ["a"], "flags": [1, 2], "tmp": 1}"#);
+        let ours = parse(r#"{"name": "svc", "port": 8080, "log": {"level": "debug", "file": "a.log"}, "hosts": ["a", "b"], "flags": [1, 3], "tmp": 1, "x": 1}"#);
+        let theirs = parse(r#"{"name": "api", "port": 9090, "log": {"level": "info", "file": "b.log"}, "hosts": ["a", "c"], "flags": [1, 4], "y": 2}"#);
+
+        let r = merge::merge3(&base, &ours, &theirs);
+        assert!(!r.is_clean());
+        let conflicts: Vec<&str> = r.conflicts.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(conflicts, vec!["/port", "/flags/1"]);
+        assert_eq!((r.conflicts[0].base.as_ref().unwrap().as_int(), r.conflicts[0].theirs.as_ref().unwrap().as_int()), (80, 9090));
+        //冲突处暂时使用ours
+        assert_eq!(r.value.to_string(), r#"{"name": "api","port": 8080,"log": {"level": "debug","file": "b.log"},"hosts": ["a","b","c"],"flags": [1,3],"x": 1,"y": 2}"#);
+
+        let r = merge::merge3_with(&base, &ours, &theirs, &PreferTheirs);
+        assert!(r.is_clean());
+        assert_eq!((r.value.int_byName("port", 0), r.value.to_string().contains(r#""flags": [1,4]"#)), (9090, true));
+
+        //自定义策略：数字取较大值，其余保留冲突
+        let max = |c: &Conflict| match (&c.ours, &c.theirs) {
+            (Some(o), Some(t)) if o.as_int() != 0 && t.as_int() != 0 => Resolution::Value(DxValue::Int(o.as_int().max(t.as_int()))),
+            _ => Resolution::Unresolved,
+        };
+        let r = merge::merge3_with(&base, &ours, &theirs, &max);
+        assert!(r.is_clean());
+        assert_eq!((r.value.int_byName("port", 0), r.value.get_byPointer("/flags/1").unwrap().as_int()), (9090, 4));
+
+        //一方删除、另一方修改是冲突，Remove策略删除该键
+        let r = merge::merge3(&parse(r#"{"a": 1}"#), &parse(r#"{}"#), &parse(r#"{"a": 2}"#));
+        assert_eq!((r.conflicts.len(), r.conflicts[0].ours.is_none()), (1, true));
+        let r = merge::merge3_with(&parse(r#"{"a": 1}"#), &parse(r#"{}"#), &parse(r#"{"a": 2}"#), &|_: &Conflict| Resolution::Remove);
+        assert_eq!(r.value.to_string(), "{}");
+        //双方都新增了同一个对象键时合并其内容
+        let r = merge::merge3(&parse("{}"), &parse(r#"{"o": {"a": 1}}"#), &parse(r#"{"o": {"b": 2}}"#));
+        assert_eq!((r.is_clean(), r.value.to_string()), (true, r#"{"o": {"a": 1,"b": 2}}"#.to_string()));
+        //超过2^53的整数按精确值比较，不会当作未修改
+        let base = parse(r#"{"id": 9007199254740993}"#);
+        let r = merge::merge3(&base, &base, &parse(r#"{"id": 9007199254740992}"#));
+        assert_eq!((r.is_clean(), r.value.to_string()), (true, r#"{"id": 9007199254740992}"#.to_string()));
+        //NaN未修改时不是冲突
+        let mut nan = DxValue::newObject();
+        nan.setKeyValue("x", DxValue::Double(f64::NAN));
+        let mut theirs = nan.clone();
+        theirs.setKeyValue("y", DxValue::Int(1));
+        let r = merge::merge3(&nan, &nan, &theirs);
+        assert!(r.is_clean());
+    }
 }
@@ -0,0 +1,302 @@
+use crate::dxvalue::DxValue;
+use crate::dxvalue::codegen::{FromDxValue, ToDxValue};
+
+#[derive(Debug, Clone)]
+pub struct Document {
+    pub head: Node,
+    pub option: Option<Option2>,
+    /// json: "self"
+    pub self_: Option<Self2>,
+    pub string: Option<String2>,
+    pub reserved: Option<Reserved>,
+}
+
+impl FromDxValue for Document {
+    fn from_dxvalue(v: &DxValue) -> Option<Self> {
+        let v = match v {
+            DxValue::Raw(raw) => raw.value()?,
+            v => v,
+        };
+        if !matches!(v, DxValue::Object(_)) {
+            return None;
+        }
+        Some(Document {
+            head: FromDxValue::from_dxvalue(v.get_byName("head")?)?,
+            option: match v.get_byName("option") { Some(x) => FromDxValue::from_dxvalue(x)?, None => None },
+            self_: match v.get_byName("self") { Some(x) => FromDxValue::from_dxvalue(x)?, None => None },
+            string: match v.get_byName("string") { Some(x) => FromDxValue::from_dxvalue(x)?, None => None },
+            reserved: match v.get_byName("reserved") { Some(x) => FromDxValue::from_dxvalue(x)?, None => None },
+        })
+    }
+}
+
+impl ToDxValue for Document {
+    fn to_dxvalue(&self) -> DxValue {
+        let mut v = DxValue::newObject();
+        v.setKeyValue("head", self.head.to_dxvalue());
+        if let Some(x) = &self.option {
+            v.setKeyValue("option", x.to_dxvalue());
+        }
+        if let Some(x) = &self.self_ {
+            v.setKeyValue("self", x.to_dxvalue());
+        }
+        if let Some(x) = &self.string {
+            v.setKeyValue("string", x.to_dxvalue());
+        }
+        if let Some(x) = &self.reserved {
+            v.setKeyValue("reserved", x.to_dxvalue());
+        }
+        v
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Reserved {
+    pub r#override: Option<i64>,
+    pub r#yield: Option<i64>,
+    pub r#final: Option<String>,
+    pub r#typeof: Option<bool>,
+    pub r#gen: Option<DxValue>,
+    pub r#macro: Option<String>,
+    pub r#priv: Option<bool>,
+}
+
+impl FromDxValue for Reserved {
+    fn from_dxvalue(v: &DxValue) -> Option<Self> {
+        let v = match v {
+            DxValue::Raw(raw) => raw.value()?,
+            v => v,
+        };
+        if !matches!(v, DxValue::Object(_)) {
+            return None;
+        }
+        Some(Reserved {
+            r#override: match v.get_byName("override") { Some(x) => FromDxValue::from_dxvalue(x)?, None => None },
+            r#yield: match v.get_byName("yield") { Some(x) => FromDxValue::from_dxvalue(x)?, None => None },
+            r#final: match v.get_byName("final") { Some(x) => FromDxValue::from_dxvalue(x)?, None => None },
+            r#typeof: match v.get_byName("typeof") { Some(x) => FromDxValue::from_dxvalue(x)?, None => None },
+            r#gen: match v.get_byName("gen") { Some(x) => FromDxValue::from_dxvalue(x)?, None => None },
+            r#macro: match v.get_byName("macro") { Some(x) => FromDxValue::from_dxvalue(x)?, None => None },
+            r#priv: match v.get_byName("priv") { Some(x) => FromDxValue::from_dxvalue(x)?, None => None },
+        })
+    }
+}
+
+impl ToDxValue for Reserved {
+    fn to_dxvalue(&self) -> DxValue {
+        let mut v = DxValue::newObject();
+        if let Some(x) = &self.r#override {
+            v.setKeyValue("override", x.to_dxvalue());
+        }
+        if let Some(x) = &self.r#yield {
+            v.setKeyValue("yield", x.to_dxvalue());
+        }
+        if let Some(x) = &self.r#final {
+            v.setKeyValue("final", x.to_dxvalue());
+        }
+        if let Some(x) = &self.r#typeof {
+            v.setKeyValue("typeof", x.to_dxvalue());
+        }
+        if let Some(x) = &self.r#gen {
+            v.setKeyValue("gen", x.to_dxvalue());
+        }
+        if let Some(x) = &self.r#macro {
+            v.setKeyValue("macro", x.to_dxvalue());
+        }
+        if let Some(x) = &self.r#priv {
+            v.setKeyValue("priv", x.to_dxvalue());
+        }
+        v
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct String2 {
+    pub s: Option<String>,
+}
+
+impl FromDxValue for String2 {
+    fn from_dxvalue(v: &DxValue) -> Option<Self> {
+        let v = match v {
+            DxValue::Raw(raw) => raw.value()?,
+            v => v,
+        };
+        if !matches!(v, DxValue::Object(_)) {
+            return None;
+        }
+        Some(String2 {
+            s: match v.get_byName("s") { Some(x) => FromDxValue::from_dxvalue(x)?, None => None },
+        })
+    }
+}
+
+impl ToDxValue for String2 {
+    fn to_dxvalue(&self) -> DxValue {
+        let mut v = DxValue::newObject();
+        if let Some(x) = &self.s {
+            v.setKeyValue("s", x.to_dxvalue());
+        }
+        v
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Self2 {
+    pub vec: Option<Vec2>,
+}
+
+impl FromDxValue for Self2 {
+    fn from_dxvalue(v: &DxValue) -> Option<Self> {
+        let v = match v {
+            DxValue::Raw(raw) => raw.value()?,
+            v => v,
+        };
+        if !matches!(v, DxValue::Object(_)) {
+            return None;
+        }
+        Some(Self2 {
+            vec: match v.get_byName("vec") { Some(x) => FromDxValue::from_dxvalue(x)?, None => None },
+        })
+    }
+}
+
+impl ToDxValue for Self2 {
+    fn to_dxvalue(&self) -> DxValue {
+        let mut v = DxValue::newObject();
+        if let Some(x) = &self.vec {
+            v.setKeyValue("vec", x.to_dxvalue());
+        }
+        v
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Vec2 {
+    pub n: Option<f64>,
+}
+
+impl FromDxValue for Vec2 {
+    fn from_dxvalue(v: &DxValue) -> Option<Self> {
+        let v = match v {
+            DxValue::Raw(raw) => raw.value()?,
+            v => v,
+        };
+        if !matches!(v, DxValue::Object(_)) {
+            return None;
+        }
+        Some(Vec2 {
+            n: match v.get_byName("n") { Some(x) => FromDxValue::from_dxvalue(x)?, None => None },
+        })
+    }
+}
+
+impl ToDxValue for Vec2 {
+    fn to_dxvalue(&self) -> DxValue {
+        let mut v = DxValue::newObject();
+        if let Some(x) = &self.n {
+            v.setKeyValue("n", x.to_dxvalue());
+        }
+        v
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Option2 {
+    pub on: Option<bool>,
+}
+
+impl FromDxValue for Option2 {
+    fn from_dxvalue(v: &DxValue) -> Option<Self> {
+        let v = match v {
+            DxValue::Raw(raw) => raw.value()?,
+            v => v,
+        };
+        if !matches!(v, DxValue::Object(_)) {
+            return None;
+        }
+        Some(Option2 {
+            on: match v.get_byName("on") { Some(x) => FromDxValue::from_dxvalue(x)?, None => None },
+        })
+    }
+}
+
+impl ToDxValue for Option2 {
+    fn to_dxvalue(&self) -> DxValue {
+        let mut v = DxValue::newObject();
+        if let Some(x) = &self.on {
+            v.setKeyValue("on", x.to_dxvalue());
+        }
+        v
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Node {
+    pub value: i64,
+    pub next: Option<Box<Node>>,
+    pub children: Option<Vec<Node>>,
+    pub parent: Option<Link>,
+}
+
+impl FromDxValue for Node {
+    fn from_dxvalue(v: &DxValue) -> Option<Self> {
+        let v = match v {
+            DxValue::Raw(raw) => raw.value()?,
+            v => v,
+        };
+        if !matches!(v, DxValue::Object(_)) {
+            return None;
+        }
+        Some(Node {
+            value: FromDxValue::from_dxvalue(v.get_byName("value")?)?,
+            next: match v.get_byName("next") { Some(x) => FromDxValue::from_dxvalue(x)?, None => None },
+            children: match v.get_byName("children") { Some(x) => FromDxValue::from_dxvalue(x)?, None => None },
+            parent: match v.get_byName("parent") { Some(x) => FromDxValue::from_dxvalue(x)?, None => None },
+        })
+    }
+}
+
+impl ToDxValue for Node {
+    fn to_dxvalue(&self) -> DxValue {
+        let mut v = DxValue::newObject();
+        v.setKeyValue("value", self.value.to_dxvalue());
+        if let Some(x) = &self.next {
+            v.setKeyValue("next", x.to_dxvalue());
+        }
+        if let Some(x) = &self.children {
+            v.setKeyValue("children", x.to_dxvalue());
+        }
+        if let Some(x) = &self.parent {
+            v.setKeyValue("parent", x.to_dxvalue());
+        }
+        v
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Link {
+    pub target: Box<Node>,
+}
+
+impl FromDxValue for Link {
+    fn from_dxvalue(v: &DxValue) -> Option<Self> {
+        let v = match v {
+            DxValue::Raw(raw) => raw.value()?,
+            v => v,
+        };
+        if !matches!(v, DxValue::Object(_)) {
+            return None;
+        }
+        Some(Link {
+            target: FromDxValue::from_dxvalue(v.get_byName("target")?)?,
+        })
+    }
+}
+
+impl ToDxValue for Link {
+    fn to_dxvalue(&self) -> DxValue {
+        let mut v = DxValue::newObject();
+        v.setKeyValue("target", self.target.to_dxvalue());
+        v
+    }
+}